@@ -1,13 +1,13 @@
-use crate::{camer_control, level::{hallway::{ControlRect, HallWay, HallWayTexData}, level::LevelState, mesh::{Mesh, MeshTex, Meshable, TileStyle}, room::{Door, DoorId, HorizontalAlign, Modifier, Room, RoomId, VerticalAlign, Wall}}, more_stolen_code::FileDialog, renderer::{self, camera::Camera, texture::{TextureData, TextureId}}, stolen_code_to_update_dependencies};
+use crate::{camer_control, level::{hallway::{ControlRect, DoorLocation, HallWay, HallWayPath, HallWayTexData, Junction, JunctionOpening, Stairs, StepSize}, level::LevelState, mesh::{Mesh, MeshTex, Meshable, TileStyle}, room::{Bevel, ColumnEnd, ColumnShape, Door, DoorId, DoorShape, Hole, HorizontalAlign, Landing, Modifier, Room, RoomId, VerticalAlign, Wall}}, more_stolen_code::FileDialog, renderer::{self, camera::Camera, texture::{TextureData, TextureId}}, stolen_code_to_update_dependencies};
 use egui::{emath, vec2, Button, CollapsingHeader, Color32, ComboBox, Context, DragValue, FontFamily, FontId, FullOutput, Grid, ImageSource, RichText, ScrollArea, Sense, Ui, Vec2, WidgetText};
 use egui_modal::Modal;
 use instant::Instant;
 use itertools::Itertools;
-use std::{borrow::Cow, cmp::Ordering, collections::HashMap, path::PathBuf};
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap, path::{Path, PathBuf}};
 use std::hash::Hash;
 use winit::{event::{DeviceEvent, ElementState, KeyEvent, MouseButton, WindowEvent}, keyboard::{KeyCode, PhysicalKey}};
 use egui_dnd::{self};
-use cgmath::{Deg, InnerSpace, Point3, Vector2, Vector3};
+use cgmath::{Deg, InnerSpace, Point3, Rad, Vector2, Vector3};
 use egui::FontDefinitions;
 use stolen_code_to_update_dependencies::{Platform, PlatformDescriptor};
 use renderer::renderstate::State;
@@ -19,7 +19,8 @@ use winit::{
     window::{Fullscreen, Window, WindowBuilder},
 };
 use crate::ModuloSignedExt;
-use super::{borrowed_toggle_switch::{self, toggle_ui}, game_folder_structure::GameData};
+use crate::export::{export_level, ExportFormat};
+use super::{borrowed_toggle_switch::{self, toggle_ui}, game_folder_structure::{GameData, LoadError}};
 
 pub struct ApplicationState{
     screen_state: ScreenState,
@@ -38,19 +39,32 @@ enum ScreenState {
         opened_file: Option<PathBuf>,
         open_file_dialog: Option<FileDialog>,
         game_data: Option<GameData>,
-        create_new:bool
+        create_new:bool,
+        //a folder that loaded with broken levels or textures, waiting on the user to open it anyway
+        partial_load: Option<Box<(PathBuf, GameData, Vec<LoadError>)>>,
     },
     Editor{
         editor_state:EditorState,
         game_data:GameData,
         folder_path:PathBuf,
+        dialogs:Box<EditorDialogs>,
     },
 }
 
+/// File dialogs the editor has open, boxed so they don't bloat `ScreenState`.
+#[derive(Default)]
+struct EditorDialogs{
+    export_dialog:Option<(FileDialog,ExportFormat)>,
+    mesh_import_dialog:Option<FileDialog>,
+    texture_import_dialog:Option<FileDialog>,
+    texture_replace_dialog:Option<(FileDialog,TextureId)>,
+}
+
 #[derive(Clone)]
 enum EditorState{
     LevelSelection{
         possible_new_level_names:HashMap<String,String>,
+        possible_new_texture_names:HashMap<TextureId,String>,
         selected_level:Option<String>,
     },
     LevelEditing{
@@ -77,6 +91,9 @@ enum SelectedItem{
     HallWay{
         hallway_index:usize,
     },
+    Junction{
+        junction_index:usize,
+    },
 }
 
 impl ApplicationState {
@@ -128,6 +145,7 @@ impl ApplicationState {
                 open_file_dialog: None,
                 game_data: None,
                 create_new:false,
+                partial_load: None,
             },
             default_tex:default_tex,
             cursor_inside:false,
@@ -153,10 +171,12 @@ impl ApplicationState {
                             h.insert(level_name.clone(), level_name.clone());
                         });
                         h
-                    }
+                    },
+                    possible_new_texture_names:HashMap::new(),
                 },
                 game_data: game_data.clone(),
-                folder_path: folder_path.to_path_buf()
+                folder_path: folder_path.to_path_buf(),
+                dialogs: Box::default(),
                 };
         }
         match &mut self.screen_state {
@@ -164,7 +184,8 @@ impl ApplicationState {
                 opened_file,
                 open_file_dialog,
                 game_data,
-                create_new
+                create_new,
+                partial_load,
             } => {
                 egui::CentralPanel::default().show(&ctx, |ui| {
                     ui.add_space(30.0);
@@ -192,6 +213,7 @@ impl ApplicationState {
                     };
 
                     let mut modal = Modal::new(ctx, "error");
+                    let load_errors_modal = Modal::new(ctx, "load errors");
 
                     if let Some(dialog) = open_file_dialog {
                         if dialog.show(ctx).selected() {
@@ -199,33 +221,70 @@ impl ApplicationState {
                                 if *create_new{
                                     *game_data = Some(GameData::new());
                                     *opened_file = Some(file.to_path_buf());
-                                    game_data.as_mut().expect("How. I litteraly just asigned a Some value to this var")
+                                    if let Err(err) = game_data.as_mut().expect("How. I litteraly just asigned a Some value to this var")
                                         .generate_new_game_folder(file.to_path_buf())
-                                        .expect("I alrealdy checked and this worked earlier");
-                                }else{
-                                    if let Some(game_data_from_path) =
-                                        GameData::generate(&file.to_path_buf()) 
                                     {
-                                        *opened_file = Some(file.to_path_buf());
-                                        *game_data = Some(game_data_from_path);
-                                    } else {
-                                        modal
-                                            .dialog()
-                                            .with_title("Invalid Folder")
-                                            .with_body(format!("Folder {} structure isn't correct or the config file is messed up :(",file.display()))
-                                            .open();
+                                        *game_data = None;
+                                        *opened_file = None;
+                                        open_save_error(ctx, file, err);
+                                    }
+                                }else{
+                                    match GameData::generate(file) {
+                                        Ok((game_data_from_path, errors)) if errors.is_empty() => {
+                                            *opened_file = Some(file.to_path_buf());
+                                            *game_data = Some(game_data_from_path);
+                                        }
+                                        Ok((game_data_from_path, errors)) => {
+                                            *partial_load = Some(Box::new((file.to_path_buf(), game_data_from_path, errors)));
+                                            load_errors_modal.open();
+                                        }
+                                        Err(err) => {
+                                            modal
+                                                .dialog()
+                                                .with_title("Invalid Folder")
+                                                .with_body(format!("Folder {} couldn't be opened:\n{}",file.display(),err))
+                                                .open();
+                                        }
                                     }
                                 }
                             }
+                            *open_file_dialog = None;
                         }
                     }
+                    load_errors_modal.show(|ui|{
+                        load_errors_modal.title(ui, "Problems Loading Folder");
+                        load_errors_modal.frame(ui, |ui|{
+                            if let Some((_, _, errors)) = partial_load.as_deref() {
+                                errors.iter().for_each(|error|{
+                                    ui.label(error.to_string());
+                                });
+                            }
+                            load_errors_modal.body(ui, "Files that failed to load are quarantined: they are left out of the editor and saving the folder moves them to its quarantine directory.");
+                        });
+                        load_errors_modal.buttons(ui, |ui|{
+                            if load_errors_modal.caution_button(ui, "Cancel").clicked(){
+                                *partial_load = None;
+                            }
+                            if load_errors_modal.suggested_button(ui, "Open with quarantine").clicked(){
+                                if let Some((file, game_data_from_path, _)) = partial_load.take().map(|partial| *partial) {
+                                    *opened_file = Some(file);
+                                    *game_data = Some(game_data_from_path);
+                                }
+                            }
+                        });
+                    });
                     modal.show_dialog();
+                    Modal::new(ctx, "save error").show_dialog();
                 });
             }
-            ScreenState::Editor { editor_state, game_data, folder_path } => {
+            ScreenState::Editor { editor_state, game_data, folder_path, dialogs } => {
+                let EditorDialogs { export_dialog, mesh_import_dialog, texture_import_dialog, texture_replace_dialog } = &mut **dialogs;
+                Modal::new(ctx, "save error").show_dialog();
+                Modal::new(ctx, "texture error").show_dialog();
                 match editor_state{
                     EditorState::LevelSelection{
                         possible_new_level_names,
+                        possible_new_texture_names,
                         selected_level
                     }=>{
                         egui::TopBottomPanel::top("top").show(ctx, |ui|{
@@ -247,10 +306,80 @@ impl ApplicationState {
                                 }
                                 if ui.button("save").clicked(){
                                     game_data.update_config();
-                                    let _ = game_data.generate_new_game_folder(folder_path.clone());
+                                    if let Err(err) = game_data.generate_new_game_folder(folder_path.clone()){
+                                        open_save_error(ctx, folder_path, err);
+                                    }
                                 }
                             });
                         });
+                        egui::SidePanel::right("textures").show(ctx, |ui|{
+                            ui.heading("Textures");
+                            egui::ScrollArea::new([false,true]).show(ui, |ui|{
+                                let unused = game_data.unused_textures();
+                                let mut to_rename: Option<TextureId> = None;
+                                let mut to_delete: Vec<TextureId> = vec![];
+                                for (name,data,extension) in game_data.textures.iter(){
+                                    ui.horizontal(|ui|{
+                                        ui.add(egui::Image::new(ImageSource::Bytes { uri: Cow::Owned(format!("bytes://{}.{}",name,extension)), bytes: egui::load::Bytes::Shared(data.clone()) }).max_width(40.));
+                                        ui.vertical(|ui|{
+                                            ui.horizontal(|ui|{
+                                                ui.label(name.as_ref());
+                                                if unused.contains(name){
+                                                    ui.label(RichText::new("unused").weak());
+                                                }
+                                            });
+                                            ui.horizontal(|ui|{
+                                                if ui.button("Rename").clicked(){
+                                                    to_rename = Some(name.clone());
+                                                }
+                                                ui.text_edit_singleline(possible_new_texture_names.entry(name.clone()).or_insert_with(||name.to_string()));
+                                            });
+                                            ui.horizontal(|ui|{
+                                                if ui.button("Replace").clicked(){
+                                                    let mut dialog = FileDialog::open_file(Some(folder_path.clone()));
+                                                    dialog.open();
+                                                    *texture_replace_dialog = Some((dialog,name.clone()));
+                                                }
+                                                if ui.add_enabled(unused.contains(name), Button::new("Delete")).clicked(){
+                                                    to_delete.push(name.clone());
+                                                }
+                                            });
+                                        });
+                                    });
+                                }
+                                if ui.add_enabled(!unused.is_empty(), Button::new("Delete Unused")).clicked(){
+                                    to_delete.extend(unused.iter().cloned());
+                                }
+                                if let Some(name) = to_rename{
+                                    let new_name: TextureId = possible_new_texture_names[&name].as_str().into();
+                                    match game_data.rename_texture(&name, new_name.clone()){
+                                        Ok(_) => {
+                                            if let Some(texture) = self.render_state.textures.remove(&name){
+                                                self.render_state.textures.insert(new_name, texture);
+                                            }
+                                            possible_new_texture_names.remove(&name);
+                                        },
+                                        Err(err) => open_texture_error(ctx, "Rename Failed", err),
+                                    }
+                                }
+                                to_delete.into_iter().for_each(|name|{
+                                    game_data.delete_texture(&name);
+                                    self.render_state.textures.remove(&name);
+                                    possible_new_texture_names.remove(&name);
+                                });
+                            });
+                        });
+                        if let Some((dialog,name)) = texture_replace_dialog{
+                            if dialog.show(ctx).selected(){
+                                if let Some(path) = dialog.path(){
+                                    match game_data.replace_texture(name, path){
+                                        Ok(_) => upload_texture(ctx, &mut self.render_state, game_data, name),
+                                        Err(err) => open_texture_error(ctx, "Replace Failed", err.context(format!("couldn't use {}",path.display()))),
+                                    }
+                                }
+                                *texture_replace_dialog = None;
+                            }
+                        }
                         egui::CentralPanel::default().show(ctx, |ui|{
                             ui.set_width(ui.available_width());
                             ui.horizontal_wrapped(|ui|{
@@ -307,29 +436,68 @@ impl ApplicationState {
                                 };
                                 if add_button("Main Menu").clicked(){
                                     screen_state_callbacks.push(Box::new(|screen_state|{
-                                        *screen_state = ScreenState::MainMenu { opened_file: None, open_file_dialog: None, game_data: None, create_new: false }
+                                        *screen_state = ScreenState::MainMenu { opened_file: None, open_file_dialog: None, game_data: None, create_new: false, partial_load: None }
                                     }));
                                 }        
                                 if add_button("Level Select").clicked(){
                                     screen_state_callbacks.push(Box::new(|screen_state|{
                                         if let ScreenState::Editor { editor_state, game_data, .. } = screen_state{
-                                            *editor_state = EditorState::LevelSelection { possible_new_level_names: game_data.levels.iter().map(|level_name|(level_name.clone(),level_name.clone())).collect(), selected_level: None };
+                                            *editor_state = EditorState::LevelSelection { possible_new_level_names: game_data.levels.iter().map(|level_name|(level_name.clone(),level_name.clone())).collect(), possible_new_texture_names: HashMap::new(), selected_level: None };
                                         }
                                     }));
                                 }                        
                                 if add_button("Save").clicked(){
                                     let level_state = level.clone();
                                     let temp: String = selected_level.clone();
+                                    let ctx = ctx.clone();
                                     screen_state_callbacks.push(Box::new(move |screen_state|{
                                         if let ScreenState::Editor { game_data, folder_path, .. }  = screen_state{
                                             *game_data.levels_data.get_mut(&temp).expect("not possible") = level_state;
-                                            game_data.update_folder(folder_path).expect("failed to save");
+                                            if let Err(err) = game_data.generate_new_game_folder(folder_path.clone()){
+                                                open_save_error(&ctx, folder_path, err);
+                                            }
                                         }
                                     }));
                                 }
+                                for (text,format) in [("Export glTF",ExportFormat::Gltf),("Export OBJ",ExportFormat::Obj)]{
+                                    if add_button(text).clicked(){
+                                        let mut dialog = FileDialog::save_file(Some(folder_path.clone())).default_filename(format!("{}.{}",selected_level,format.extension()));
+                                        dialog.open();
+                                        *export_dialog = Some((dialog,format));
+                                    }
+                                }
+                                if add_button("Import Texture").clicked(){
+                                    let mut dialog = FileDialog::open_file(Some(folder_path.clone()));
+                                    dialog.open();
+                                    *texture_import_dialog = Some(dialog);
+                                }
                             });
                         });
-                        fn add_or_delete<T,U>(ui:&mut Ui, iter:&mut HashMap<U,T>, mut callback:impl FnMut(&mut Ui,&U,&mut T), default:T,order:impl FnMut(&(&U,&mut T),&(&U,&mut T))->Ordering)where U:Default + Hash + Eq + Clone{
+                        if let Some(dialog) = texture_import_dialog{
+                            if dialog.show(ctx).selected(){
+                                if let Some(path) = dialog.path(){
+                                    import_texture(ctx, &mut self.render_state, game_data, path);
+                                }
+                                *texture_import_dialog = None;
+                            }
+                        }
+                        let mut export_modal = Modal::new(ctx, "export error");
+                        if let Some((dialog,format)) = export_dialog{
+                            if dialog.show(ctx).selected(){
+                                if let Some(path) = dialog.path(){
+                                    if let Err(err) = export_level(*format, level, game_data, folder_path, path){
+                                        export_modal
+                                            .dialog()
+                                            .with_title("Export Failed")
+                                            .with_body(format!("Couldn't export to {}: {}",path.display(),err))
+                                            .open();
+                                    }
+                                }
+                                *export_dialog = None;
+                            }
+                        }
+                        export_modal.show_dialog();
+                        fn add_or_delete<T,U>(ui:&mut Ui, iter:&mut HashMap<U,T>, mut callback:impl FnMut(&mut Ui,&U,&mut T), default:T,order:impl FnMut(&(&U,&mut T),&(&U,&mut T))->Ordering,next_free:impl FnOnce(&HashMap<U,T>)->U)where U:Hash + Eq + Clone{
                             let mut to_add = false;
                             let mut to_delete: Option<U> = None;
                             iter.iter_mut().sorted_by(order).for_each(|(i,value)|{
@@ -347,14 +515,11 @@ impl ApplicationState {
                                 iter.remove(&i);
                             }
                             if to_add{
-                                let mut temp = U::default();
-                                while iter.contains_key(&temp){
-                                    temp = U::default();
-                                }
-                                iter.insert(temp, default);
+                                iter.insert(next_free(iter), default);
                             }
                         }
-                        fn add_or_delete2<T>(ui:&mut Ui, iter:&mut Vec<T>, mut callback:impl FnMut(&mut Ui,usize,&T), default:&T)where T:Clone{
+                        //gives back the index of the item that was deleted
+                        fn add_or_delete2<T>(ui:&mut Ui, iter:&mut Vec<T>, mut callback:impl FnMut(&mut Ui,usize,&T), default:&T)->Option<usize> where T:Clone{
                             let mut to_add: bool = false;
                             let mut to_delete: Option<usize> = None;
                             iter.iter().enumerate().for_each(|(i,value)|{
@@ -374,13 +539,19 @@ impl ApplicationState {
                             if to_add{
                                 iter.push(default.clone());
                             }
+                            to_delete
                         }
                         egui::SidePanel::left("selector").show_animated(ctx,self.interacting_with_ui, |ui|{
                             egui::ScrollArea::new([false,true]).show(ui, |ui|{
                                 CollapsingHeader::new(RichText::new("Rooms").heading()).default_open(true).show(ui, |ui|{
                                     let default_tex = MeshTex::new(TextureData::new(&self.render_state.default_texture, "default".into()),TileStyle::tile_scale(1., true));
                                     let room_callback = |ui:&mut Ui,i: &RoomId,room: &mut Room|{
-                                        ui.collapsing(format!("Room: {}",&room.name), |ui|{
+                                        let validity = room.validate();
+                                        let mut title = RichText::new(format!("Room: {}",&room.name));
+                                        if validity.is_err(){
+                                            title = title.color(Color32::RED);
+                                        }
+                                        let response = ui.collapsing(title, |ui|{
                                             if ui.label("Room").clicked(){
                                                 let i2 = i.clone();
                                                 screen_state_callbacks.push(Box::new(move |screen_state|{
@@ -394,18 +565,30 @@ impl ApplicationState {
                                                     .selected_text(match &new_moddifer{
                                                         crate::level::room::Modifier::Ramp { .. } => "Ramp",
                                                         crate::level::room::Modifier::Cliff { .. } => "Extend",
+                                                        crate::level::room::Modifier::Pit { .. } => "Pit",
                                                         crate::level::room::Modifier::Disc { .. } => "Platform",
+                                                        crate::level::room::Modifier::StaticMesh { .. } => "Prop",
+                                                        crate::level::room::Modifier::Stairs { .. } => "Stairs",
+                                                        crate::level::room::Modifier::Column { .. } => "Column",
                                                     })
                                                     .show_ui(ui, |ui|{
-                                                    ui.selectable_value(new_moddifer, Modifier::Disc { pos: Vector3::new(0., 0., 0.), size: Vector3::new(1., 1., 1.), sides: vec![default_tex.clone(),default_tex.clone(),default_tex.clone(),default_tex.clone(),default_tex.clone()], dir: Deg(0.), top_tex: default_tex.clone(), bottom_tex: default_tex.clone() }, "Platform");
+                                                    ui.selectable_value(new_moddifer, Modifier::Disc { pos: Vector3::new(0., 0., 0.), size: Vector3::new(1., 1., 1.), sides: vec![default_tex.clone(),default_tex.clone(),default_tex.clone(),default_tex.clone(),default_tex.clone()], dir: Deg(0.), top_tex: default_tex.clone(), bottom_tex: default_tex.clone(), top_size: None, bevel: None }, "Platform");
                                                     ui.selectable_value(new_moddifer, Modifier::Ramp { pos: Vector3::new(0., 0., 0.), size: Vector3::new(1., 1., 1.), ramp_texture: default_tex.clone(),dir:Deg(0.), wall_texture: default_tex.clone(), bottom_texture: default_tex.clone() },"Ramp");
-                                                    ui.selectable_value(new_moddifer, Modifier::Cliff {walls: vec![Wall {local_pos: Vector2::new(-1., -1.),wall_texture: default_tex.clone(),},Wall {local_pos: Vector2::new(1., -1.),wall_texture: default_tex.clone(),},Wall {local_pos: Vector2::new(1., 1.),wall_texture: default_tex.clone(),},Wall {local_pos: Vector2::new(-1., 1.),wall_texture: default_tex.clone(),},],on_roof: false,height: 1.,floor_texture: default_tex.clone(),}, "Extend");
+                                                    ui.selectable_value(new_moddifer, Modifier::Cliff {walls: vec![Wall::new(Vector2::new(-1., -1.), default_tex.clone()),Wall::new(Vector2::new(1., -1.), default_tex.clone()),Wall::new(Vector2::new(1., 1.), default_tex.clone()),Wall::new(Vector2::new(-1., 1.), default_tex.clone()),],on_roof: false,height: 1.,floor_texture: default_tex.clone(),}, "Extend");
+                                                    ui.selectable_value(new_moddifer, Modifier::Pit {walls: vec![Wall::new(Vector2::new(-1., -1.), default_tex.clone()),Wall::new(Vector2::new(1., -1.), default_tex.clone()),Wall::new(Vector2::new(1., 1.), default_tex.clone()),Wall::new(Vector2::new(-1., 1.), default_tex.clone()),],on_roof: false,depth: 1.,floor_texture: default_tex.clone(),}, "Pit");
+                                                    ui.selectable_value(new_moddifer, Modifier::StaticMesh { file: game_data.meshes.first().map(|(name,_)|name.clone()).unwrap_or_default(), pos: Vector3::new(0., 0., 0.), dir: Deg(0.), scale: Vector3::new(1., 1., 1.), texture: default_tex.clone(), data: None }, "Prop");
+                                                    ui.selectable_value(new_moddifer, Modifier::Stairs { pos: Vector3::new(0., 0., 0.), dir: Deg(0.), width: 1., rise: 1., run: 2., steps: 5, stringers: None, landing: None, tread_texture: default_tex.clone(), riser_texture: default_tex.clone(), side_texture: default_tex.clone() }, "Stairs");
+                                                    ui.selectable_value(new_moddifer, Modifier::Column { pos: Vector2::new(0., 0.), dir: Deg(0.), radius: 0.25, shape: ColumnShape::Round { segments: 16 }, base: None, capital: None, count: (1, 1), spacing: Vector2::new(2., 2.), shaft_texture: default_tex.clone(), cap_texture: default_tex.clone() }, "Column");
                                                 });
                                                 let moddifer_callback = |ui: &mut Ui,j,moddifier:&Modifier|{
                                                     if ui.label(match &moddifier{
                                                         crate::level::room::Modifier::Ramp { .. } => "Ramp",
                                                         crate::level::room::Modifier::Cliff { .. } => "Extend",
+                                                        crate::level::room::Modifier::Pit { .. } => "Pit",
                                                         crate::level::room::Modifier::Disc { .. } => "Platform",
+                                                        crate::level::room::Modifier::StaticMesh { .. } => "Prop",
+                                                        crate::level::room::Modifier::Stairs { .. } => "Stairs",
+                                                        crate::level::room::Modifier::Column { .. } => "Column",
                                                     }).clicked(){
                                                         let i2 = i.clone();
                                                         screen_state_callbacks.push(Box::new(move |screen_state|{
@@ -429,16 +612,28 @@ impl ApplicationState {
                                                         }));
                                                     };
                                                 };
-                                                add_or_delete(ui, &mut room.doors, door_callback, Door { wall: 0, offset: Vector2::new(0., 0.), size: Vector2::new(1., 3.), center: (VerticalAlign::Bottom,HorizontalAlign::Center) },|a,b|{a.0.cmp(b.0)});
+                                                add_or_delete(ui, &mut room.doors, door_callback, Door { wall: 0, offset: Vector2::new(0., 0.), size: Vector2::new(1., 3.), center: (VerticalAlign::Bottom,HorizontalAlign::Center), shape: DoorShape::Rectangle },|a,b|{a.0.cmp(b.0)},|doors|{DoorId::next_free(doors, &mut room.last_door_id)});
                                             });
                                         });
+                                        if let Err(err) = validity{
+                                            response.header_response.on_hover_text(err.to_string());
+                                        }
                                     };
                                     let new_name = level.rooms.iter().fold(1, |acc, room|{if room.1.name.starts_with("New Room") {acc+1}else{acc}});
-                                    add_or_delete(ui, &mut level.rooms, room_callback, Room::new(format!("New Room {}",new_name), Vector3::new(0., 0., 0.), Deg(0.), 5., default_tex.clone(), default_tex.clone(), default_tex.clone()),|a,b|{a.1.name.to_lowercase().cmp(&b.1.name.to_lowercase())});
+                                    add_or_delete(ui, &mut level.rooms, room_callback, Room::new(format!("New Room {}",new_name), Vector3::new(0., 0., 0.), Deg(0.), 5., default_tex.clone(), default_tex.clone(), default_tex.clone()),|a,b|{a.1.name.to_lowercase().cmp(&b.1.name.to_lowercase())},|rooms|{RoomId::next_free(rooms, &mut level.last_room_id)});
                                 });
                                 CollapsingHeader::new(RichText::new("Hallways").heading()).default_open(true).show(ui,|ui|{
-                                    let hallway_callback = |ui:&mut Ui,i: usize,_hallway: &HallWay|{
-                                        if ui.label(format!("Hallway {}",i+1)).clicked(){
+                                    let hallway_callback = |ui:&mut Ui,i: usize,hallway: &HallWay|{
+                                        let validity = hallway.try_mesh();
+                                        let mut title = RichText::new(format!("Hallway {}",i+1));
+                                        if validity.is_err(){
+                                            title = title.color(Color32::RED);
+                                        }
+                                        let mut response = ui.label(title);
+                                        if let Err(err) = validity{
+                                            response = response.on_hover_text(err.to_string());
+                                        }
+                                        if response.clicked(){
                                             screen_state_callbacks.push(Box::new(move |screen_state|{
                                                 if let ScreenState::Editor { editor_state:EditorState::LevelEditing {selected_item, .. } , .. } = screen_state{
                                                     *selected_item = Some(SelectedItem::HallWay { hallway_index: i });
@@ -448,6 +643,20 @@ impl ApplicationState {
                                     };
                                     add_or_delete2(ui, &mut level.hallways, hallway_callback, &HallWay::new(ControlRect::new(Vector3::new(0., 0., 0.), Deg(0.), Vector2::new(1.,3.)), ControlRect::new(Vector3::new(0., 0., 0.), Deg(0.), Vector2::new(1.,3.)), HallWayTexData::all(MeshTex::new(self.default_tex.clone(), TileStyle::tile_scale(1., true)))))
 
+                                });
+                                CollapsingHeader::new(RichText::new("Junctions").heading()).default_open(true).show(ui,|ui|{
+                                    let junction_callback = |ui:&mut Ui,i: usize,_junction: &Junction|{
+                                        if ui.label(format!("Junction {}",i+1)).clicked(){
+                                            screen_state_callbacks.push(Box::new(move |screen_state|{
+                                                if let ScreenState::Editor { editor_state:EditorState::LevelEditing {selected_item, .. } , .. } = screen_state{
+                                                    *selected_item = Some(SelectedItem::Junction { junction_index: i });
+                                                };
+                                            }));
+                                        };
+                                    };
+                                    if let Some(i) = add_or_delete2(ui, &mut level.junctions, junction_callback, &Junction::new(Vector3::new(0., 0., 0.), 1., 3., MeshTex::new(self.default_tex.clone(), TileStyle::tile_scale(1., true)))){
+                                        level.junction_removed(i);
+                                    }
                                 })
                             });
                         });         
@@ -541,12 +750,17 @@ impl ApplicationState {
                                                     ui.add(egui::Label::new("Name:").wrap(false));
                                                     ui.text_edit_singleline(&mut room.name);
                                                 });
+                                                //the room isn't drawn until this is fixed
+                                                if let Err(err) = room.validate(){
+                                                    ui.colored_label(Color32::RED, format!("Invalid room, {}", err));
+                                                }
                                                 ui.collapsing("Position", |ui|{                                                
                                                     add_drag_value(ui,"X:",&mut room.position.x,0.1);
                                                     add_drag_value(ui,"Y:",&mut room.position.y,0.1);
                                                     add_drag_value(ui,"Z:",&mut room.position.z,0.1);
                                                     add_drag_value(ui,"Rot:",&mut room.rotation.0,1.);
                                                     add_drag_value(ui,"Height",&mut room.height, 0.1);
+                                                    add_drag_value(ui,"Wall Thickness",&mut room.wall_thickness, 0.05);
                                                 });
                                                 ui.collapsing("Textures", |ui|{
                                                     add_texture_controls(ui, "Floor texture",&mut room.floor_texture);
@@ -555,11 +769,36 @@ impl ApplicationState {
                                                 ui.collapsing("Walls", |ui|{
                                                     let mut wall_to_remove=None;
                                                     let mut wall_to_add=None;
+                                                    let room_height = room.height;
+                                                    let room_thickness = room.wall_thickness;
                                                     (0..room.walls.len()).into_iter().for_each(|i|{
                                                         let wall = &mut room.walls[i];
                                                         ui.collapsing(format!("Wall {i}"), |ui|{
                                                             add_drag_value(ui, "X:", &mut wall.local_pos.x, 0.1);
                                                             add_drag_value(ui, "Y:", &mut wall.local_pos.y, 0.1);
+                                                            ui.horizontal(|ui|{
+                                                                let mut custom_height = wall.height.is_some();
+                                                                ui.label("Own Roof Height:");
+                                                                toggle_ui(ui, &mut custom_height);
+                                                                if custom_height != wall.height.is_some(){
+                                                                    wall.height = custom_height.then_some(room_height);
+                                                                }
+                                                            });
+                                                            if let Some(height) = &mut wall.height{
+                                                                add_drag_value(ui, "Roof Height:", height, 0.1);
+                                                            }
+                                                            add_drag_value(ui, "Floor Offset:", &mut wall.floor_offset, 0.1);
+                                                            ui.horizontal(|ui|{
+                                                                let mut custom_thickness = wall.thickness.is_some();
+                                                                ui.label("Own Thickness:");
+                                                                toggle_ui(ui, &mut custom_thickness);
+                                                                if custom_thickness != wall.thickness.is_some(){
+                                                                    wall.thickness = custom_thickness.then_some(room_thickness);
+                                                                }
+                                                            });
+                                                            if let Some(thickness) = &mut wall.thickness{
+                                                                add_drag_value(ui, "Thickness:", thickness, 0.05);
+                                                            }
                                                             add_texture_controls(ui,"Texture",&mut wall.wall_texture);
                                                             ui.horizontal(|ui|{  
                                                                 if ui.button("−").clicked(){
@@ -571,16 +810,64 @@ impl ApplicationState {
                                                             });
                                                         });
                                                     });
-                                                    if let Some(i) = wall_to_remove{
+                                                    //the floor needs at least a triangle
+                                                    if let Some(i) = wall_to_remove.filter(|_|room.walls.len()>3){
                                                         room.walls.remove(i);
                                                     }
                                                     if let Some(i) = wall_to_add{
                                                         room.walls.insert(i,Wall::new((room.walls[((i as isize -1)%(room.walls.len() as isize)) as usize].local_pos + room.walls[((i as isize)%(room.walls.len() as isize)) as usize].local_pos)/2., room.walls[((i as isize -1)%(room.walls.len() as isize)) as usize].wall_texture.clone()));
                                                     }
                                                 });
+                                                let rim_texture = room.walls.first().map(|wall|wall.wall_texture.clone()).unwrap_or(room.floor_texture.clone());
+                                                for (label,holes) in [("Floor Holes",&mut room.floor_holes),("Roof Holes",&mut room.roof_holes)]{
+                                                    ui.collapsing(label, |ui|{
+                                                        let mut hole_to_remove=None;
+                                                        holes.iter_mut().enumerate().for_each(|(i,hole)|{
+                                                            ui.collapsing(format!("Hole {i}"), |ui|{
+                                                                add_drag_value(ui, "Depth:", &mut hole.depth, 0.1);
+                                                                add_texture_controls(ui,"Rim Texture",&mut hole.rim_texture);
+                                                                let mut point_to_remove=None;
+                                                                let mut point_to_add=None;
+                                                                (0..hole.points.len()).for_each(|j|{
+                                                                    let point = &mut hole.points[j];
+                                                                    ui.collapsing(format!("Point {j}"), |ui|{
+                                                                        add_drag_value(ui, "X:", &mut point.x, 0.1);
+                                                                        add_drag_value(ui, "Y:", &mut point.y, 0.1);
+                                                                        ui.horizontal(|ui|{
+                                                                            if ui.button("−").clicked(){
+                                                                                point_to_remove = Some(j);
+                                                                            };
+                                                                            if ui.button("+").clicked(){
+                                                                                point_to_add = Some(j+1);
+                                                                            };
+                                                                        });
+                                                                    });
+                                                                });
+                                                                //a hole needs at least a triangle
+                                                                if let Some(j) = point_to_remove.filter(|_|hole.points.len()>3){
+                                                                    hole.points.remove(j);
+                                                                }
+                                                                if let Some(j) = point_to_add{
+                                                                    let len = hole.points.len();
+                                                                    hole.points.insert(j,(hole.points[j-1] + hole.points[j%len])/2.);
+                                                                }
+                                                                if ui.button("Remove Hole").clicked(){
+                                                                    hole_to_remove = Some(i);
+                                                                }
+                                                            });
+                                                        });
+                                                        if let Some(i) = hole_to_remove{
+                                                            holes.remove(i);
+                                                        }
+                                                        if ui.button("Add Hole").clicked(){
+                                                            holes.push(Hole::new(Vector2::new(0., 0.), rim_texture.clone()));
+                                                        }
+                                                    });
+                                                }
                                             }
                                         },
                                         SelectedItem::Modifer { room_index, modifer_index } => {
+                                            let wall_lines = level.rooms.get(room_index).map(|room|room.walls.iter().circular_tuple_windows().map(|(a,b)|(a.local_pos,b.local_pos)).collect_vec()).unwrap_or_default();
                                             if let Some(modifer) = level.rooms.get_mut(&room_index).and_then(|room|{room.moddifiers.get_mut(*modifer_index)}){                                                
                                                 match modifer{
                                                     crate::level::room::Modifier::Ramp { pos, dir, size, ramp_texture, wall_texture, bottom_texture } => {
@@ -633,7 +920,38 @@ impl ApplicationState {
                                                             }
                                                         });
                                                     },
-                                                    crate::level::room::Modifier::Disc { pos, size, sides, dir, top_tex, bottom_tex } => {
+                                                    crate::level::room::Modifier::Pit { walls, on_roof, depth, floor_texture } => {
+                                                        toggle_ui(ui, on_roof);
+                                                        add_drag_value(ui, "Depth:", depth, 0.1);
+                                                        add_texture_controls(ui,"Floor Texture",floor_texture);
+                                                        ui.collapsing("Walls", |ui|{
+                                                            let mut wall_to_remove=None;
+                                                            let mut wall_to_add=None;
+                                                            (0..walls.len()).for_each(|i|{
+                                                                let wall = &mut walls[i];
+                                                                ui.collapsing(format!("Wall {i}"), |ui|{
+                                                                    add_drag_value(ui, "X:", &mut wall.local_pos.x, 0.1);
+                                                                    add_drag_value(ui, "Y:", &mut wall.local_pos.y, 0.1);
+                                                                    add_texture_controls(ui,"Texture",&mut wall.wall_texture);
+                                                                    ui.horizontal(|ui|{  
+                                                                        if ui.button("−").clicked(){
+                                                                            wall_to_remove = Some(i);
+                                                                        };
+                                                                        if ui.button("+").clicked(){
+                                                                            wall_to_add = Some(i+1);
+                                                                        };
+                                                                    });
+                                                                });
+                                                            });
+                                                            if let Some(i) = wall_to_remove.filter(|_|walls.len()>3){
+                                                                walls.remove(i);
+                                                            }
+                                                            if let Some(i) = wall_to_add{
+                                                                walls.insert(i,Wall::new((walls[((i as isize -1)%(walls.len() as isize)) as usize].local_pos + walls[((i as isize)%(walls.len() as isize)) as usize].local_pos)/2., walls[((i as isize -1)%(walls.len() as isize)) as usize].wall_texture.clone()));
+                                                            }
+                                                        });
+                                                    },
+                                                    crate::level::room::Modifier::Disc { pos, size, sides, dir, top_tex, bottom_tex, top_size, bevel } => {
                                                         ui.collapsing("Position", |ui|{                                                        
                                                             add_drag_value(ui, "X:", &mut pos.x, 0.1);
                                                             add_drag_value(ui, "Y:", &mut pos.y, 0.1);
@@ -645,6 +963,32 @@ impl ApplicationState {
                                                             add_drag_value(ui, "Y:", &mut size.y, 0.1);
                                                             add_drag_value(ui, "Z:", &mut size.z, 0.1);
                                                         });
+                                                        ui.horizontal(|ui|{
+                                                            let mut own_top = top_size.is_some();
+                                                            ui.label("Own Top Size:");
+                                                            toggle_ui(ui, &mut own_top);
+                                                            if own_top != top_size.is_some(){
+                                                                *top_size = own_top.then_some(Vector2::new(size.x, size.z));
+                                                            }
+                                                        });
+                                                        if let Some(top_size) = top_size{
+                                                            ui.collapsing("Top Size", |ui|{
+                                                                add_drag_value(ui, "X:", &mut top_size.x, 0.1);
+                                                                add_drag_value(ui, "Z:", &mut top_size.y, 0.1);
+                                                            });
+                                                        }
+                                                        ui.horizontal(|ui|{
+                                                            let mut has_bevel = bevel.is_some();
+                                                            ui.label("Bevel:");
+                                                            toggle_ui(ui, &mut has_bevel);
+                                                            if has_bevel != bevel.is_some(){
+                                                                *bevel = has_bevel.then_some(Bevel { width: 0.1, height: 0.1 });
+                                                            }
+                                                        });
+                                                        if let Some(bevel) = bevel{
+                                                            add_drag_value(ui, "Bevel Width:", &mut bevel.width, 0.01);
+                                                            add_drag_value(ui, "Bevel Height:", &mut bevel.height, 0.01);
+                                                        }
                                                         ui.collapsing("Texture", |ui|{
                                                             add_texture_controls(ui,"Top Texture",top_tex);
                                                             add_texture_controls(ui,"Bottom Texture",bottom_tex);
@@ -673,6 +1017,165 @@ impl ApplicationState {
                                                             }
                                                         });
                                                     },
+                                                    crate::level::room::Modifier::StaticMesh { file, pos, dir, scale, texture, .. } => {
+                                                        ui.horizontal(|ui|{
+                                                            ComboBox::from_label("File").selected_text(file.clone()).show_ui(ui, |ui|{
+                                                                game_data.meshes.iter().for_each(|(name,_)|{
+                                                                    ui.selectable_value(file, name.clone(), name);
+                                                                });
+                                                            });
+                                                            if ui.button("Import").clicked(){
+                                                                let mut dialog = FileDialog::open_file(Some(folder_path.clone()));
+                                                                dialog.open();
+                                                                *mesh_import_dialog = Some(dialog);
+                                                            }
+                                                        });
+                                                        ui.collapsing("Position", |ui|{
+                                                            add_drag_value(ui, "X:", &mut pos.x, 0.1);
+                                                            add_drag_value(ui, "Y:", &mut pos.y, 0.1);
+                                                            add_drag_value(ui, "Z:", &mut pos.z, 0.1);
+                                                            add_drag_value(ui, "Rot:", &mut dir.0, 1.0);
+                                                        });
+                                                        ui.collapsing("Scale", |ui|{
+                                                            add_drag_value(ui, "X:", &mut scale.x, 0.01);
+                                                            add_drag_value(ui, "Y:", &mut scale.y, 0.01);
+                                                            add_drag_value(ui, "Z:", &mut scale.z, 0.01);
+                                                        });
+                                                        add_texture_controls(ui,"Texture",texture);
+                                                    },
+                                                    crate::level::room::Modifier::Stairs { pos, dir, width, rise, run, steps, stringers, landing, tread_texture, riser_texture, side_texture } => {
+                                                        ui.collapsing("Position", |ui|{
+                                                            add_drag_value(ui, "X:", &mut pos.x, 0.1);
+                                                            add_drag_value(ui, "Y:", &mut pos.y, 0.1);
+                                                            add_drag_value(ui, "Z:", &mut pos.z, 0.1);
+                                                            add_drag_value(ui, "Rot:", &mut dir.0, 1.0);
+                                                        });
+                                                        ui.collapsing("Size", |ui|{
+                                                            add_drag_value(ui, "Width:", width, 0.1);
+                                                            add_drag_value(ui, "Rise:", rise, 0.1);
+                                                            add_drag_value(ui, "Run:", run, 0.1);
+                                                            add_drag_value(ui, "Steps:", steps, 0.1);
+                                                        });
+                                                        ui.horizontal(|ui|{
+                                                            let mut has_stringers = stringers.is_some();
+                                                            ui.label("Stringers:");
+                                                            toggle_ui(ui, &mut has_stringers);
+                                                            if has_stringers != stringers.is_some(){
+                                                                *stringers = has_stringers.then_some(0.1);
+                                                            }
+                                                        });
+                                                        if let Some(thickness) = stringers{
+                                                            add_drag_value(ui, "Thickness:", thickness, 0.01);
+                                                        }
+                                                        ui.horizontal(|ui|{
+                                                            let mut has_landing = landing.is_some();
+                                                            ui.label("Landings:");
+                                                            toggle_ui(ui, &mut has_landing);
+                                                            if has_landing != landing.is_some(){
+                                                                *landing = has_landing.then(||Landing { every: (*steps/2).max(1), depth: 1. });
+                                                            }
+                                                        });
+                                                        if let Some(landing) = landing{
+                                                            add_drag_value(ui, "Every:", &mut landing.every, 0.1);
+                                                            add_drag_value(ui, "Depth:", &mut landing.depth, 0.1);
+                                                        }
+                                                        ui.collapsing("Textures", |ui|{
+                                                            add_texture_controls(ui,"Tread Texture",tread_texture);
+                                                            add_texture_controls(ui,"Riser Texture",riser_texture);
+                                                            add_texture_controls(ui,"Side Texture",side_texture);
+                                                        });
+                                                    },
+                                                    crate::level::room::Modifier::Column { pos, dir, radius, shape, base, capital, count, spacing, shaft_texture, cap_texture } => {
+                                                        fn add_column_end_controls(ui:&mut Ui,name:&str,end:&mut Option<ColumnEnd>){
+                                                            ui.horizontal(|ui|{
+                                                                let mut has_end = end.is_some();
+                                                                ui.label(format!("{name}:"));
+                                                                toggle_ui(ui, &mut has_end);
+                                                                if has_end != end.is_some(){
+                                                                    *end = has_end.then(||ColumnEnd::new(0.3));
+                                                                }
+                                                            });
+                                                            if let Some(end) = end{
+                                                                ui.collapsing(name, |ui|{
+                                                                    add_drag_value(ui, "Height:", &mut end.height, 0.05);
+                                                                    let mut point_to_remove = None;
+                                                                    let mut point_to_add = None;
+                                                                    end.profile.iter_mut().enumerate().for_each(|(i,point)|{
+                                                                        ui.horizontal(|ui|{
+                                                                            add_drag_value(ui, "Width:", &mut point.x, 0.05);
+                                                                            add_drag_value(ui, "Up:", &mut point.y, 0.05);
+                                                                            if ui.button("−").clicked(){
+                                                                                point_to_remove = Some(i);
+                                                                            };
+                                                                            if ui.button("+").clicked(){
+                                                                                point_to_add = Some(i+1);
+                                                                            };
+                                                                        });
+                                                                    });
+                                                                    if let Some(i) = point_to_remove{
+                                                                        end.profile.remove(i);
+                                                                    }
+                                                                    if let Some(i) = point_to_add{
+                                                                        let point = end.profile[i-1];
+                                                                        end.profile.insert(i, end.profile.get(i).map_or(point, |next|(point + next)/2.));
+                                                                    }
+                                                                    if end.profile.is_empty() && ui.button("+").clicked(){
+                                                                        end.profile.push(Vector2::new(1., 1.));
+                                                                    }
+                                                                });
+                                                            }
+                                                        }
+                                                        ui.collapsing("Position", |ui|{
+                                                            add_drag_value(ui, "X:", &mut pos.x, 0.1);
+                                                            add_drag_value(ui, "Y:", &mut pos.y, 0.1);
+                                                            add_drag_value(ui, "Rot:", &mut dir.0, 1.0);
+                                                        });
+                                                        ui.collapsing("Shape", |ui|{
+                                                            ComboBox::from_label("Cross Section")
+                                                                .selected_text(match shape{
+                                                                    ColumnShape::Square => "Square",
+                                                                    ColumnShape::Sided { .. } => "Sided",
+                                                                    ColumnShape::Round { .. } => "Round",
+                                                                })
+                                                                .show_ui(ui, |ui|{
+                                                                    ui.selectable_value(shape, ColumnShape::Square, "Square");
+                                                                    ui.selectable_value(shape, ColumnShape::Sided { sides: 6 }, "Sided");
+                                                                    ui.selectable_value(shape, ColumnShape::Round { segments: 16 }, "Round");
+                                                                });
+                                                            match shape{
+                                                                ColumnShape::Square => {},
+                                                                ColumnShape::Sided { sides } => add_drag_value(ui, "Sides:", sides, 0.1),
+                                                                ColumnShape::Round { segments } => add_drag_value(ui, "Segments:", segments, 0.1),
+                                                            }
+                                                            add_drag_value(ui, "Radius:", radius, 0.05);
+                                                            add_column_end_controls(ui, "Base", base);
+                                                            add_column_end_controls(ui, "Capital", capital);
+                                                        });
+                                                        ui.collapsing("Repeat", |ui|{
+                                                            add_drag_value(ui, "Count X:", &mut count.0, 0.1);
+                                                            add_drag_value(ui, "Count Y:", &mut count.1, 0.1);
+                                                            add_drag_value(ui, "Spacing X:", &mut spacing.x, 0.1);
+                                                            add_drag_value(ui, "Spacing Y:", &mut spacing.y, 0.1);
+                                                            ui.label("Line up in front of:");
+                                                            wall_lines.iter().enumerate().for_each(|(i,(start,end))|{
+                                                                if ui.button(format!("Wall {i}")).clicked(){
+                                                                    let along = (end - start).normalize();
+                                                                    let widest = base.iter().chain(capital.iter()).flat_map(|column_end|column_end.profile.iter().map(|point|point.x)).fold(1f32, f32::max);
+                                                                    //walls face to their left
+                                                                    let inset = (Vector2::new(-along.y, along.x) + along) * *radius * widest;
+                                                                    let (first, last) = (start + inset, end + inset - along * 2. * *radius * widest);
+                                                                    *pos = first;
+                                                                    *dir = Rad((last - first).y.atan2((last - first).x)).into();
+                                                                    count.1 = 1;
+                                                                    spacing.x = if count.0 > 1 {(last - first).magnitude() / (count.0 - 1) as f32} else {0.};
+                                                                }
+                                                            });
+                                                        });
+                                                        ui.collapsing("Textures", |ui|{
+                                                            add_texture_controls(ui,"Shaft Texture",shaft_texture);
+                                                            add_texture_controls(ui,"Cap Texture",cap_texture);
+                                                        });
+                                                    },
                                                 }
                                             }
                                         },
@@ -710,9 +1213,94 @@ impl ApplicationState {
                                                     add_drag_value(ui, "X:", &mut door.size.x, 0.01);
                                                     add_drag_value(ui, "Y:", &mut door.size.y, 0.01);
                                                 });
+                                                ui.collapsing("Shape", |ui|{
+                                                    ComboBox::from_label("Shape")
+                                                        .selected_text(match &door.shape{
+                                                            DoorShape::Rectangle => "Rectangle",
+                                                            DoorShape::Arch { .. } => "Arch",
+                                                            DoorShape::Circle { .. } => "Circle",
+                                                            DoorShape::Custom(_) => "Custom",
+                                                        })
+                                                        .show_ui(ui, |ui|{
+                                                            ui.selectable_value(&mut door.shape, DoorShape::Rectangle, "Rectangle");
+                                                            ui.selectable_value(&mut door.shape, DoorShape::Arch { segments: 8 }, "Arch");
+                                                            ui.selectable_value(&mut door.shape, DoorShape::Circle { segments: 16 }, "Circle");
+                                                            //starts as a pointed arch
+                                                            ui.selectable_value(&mut door.shape, DoorShape::Custom(vec![Vector2::new(0., 0.),Vector2::new(1., 0.),Vector2::new(1., 0.6),Vector2::new(0.5, 1.),Vector2::new(0., 0.6)]), "Custom");
+                                                        });
+                                                    match &mut door.shape{
+                                                        DoorShape::Rectangle => {},
+                                                        DoorShape::Arch { segments } | DoorShape::Circle { segments } => {
+                                                            add_drag_value(ui, "Segments:", segments, 0.1);
+                                                        },
+                                                        DoorShape::Custom(points) => {
+                                                            let mut point_to_remove=None;
+                                                            let mut point_to_add=None;
+                                                            (0..points.len()).for_each(|j|{
+                                                                let point = &mut points[j];
+                                                                ui.collapsing(format!("Point {j}"), |ui|{
+                                                                    add_drag_value(ui, "X:", &mut point.x, 0.01);
+                                                                    add_drag_value(ui, "Y:", &mut point.y, 0.01);
+                                                                    ui.horizontal(|ui|{
+                                                                        if ui.button("−").clicked(){
+                                                                            point_to_remove = Some(j);
+                                                                        };
+                                                                        if ui.button("+").clicked(){
+                                                                            point_to_add = Some(j+1);
+                                                                        };
+                                                                    });
+                                                                });
+                                                            });
+                                                            if let Some(j) = point_to_remove.filter(|_|points.len()>3){
+                                                                points.remove(j);
+                                                            }
+                                                            if let Some(j) = point_to_add{
+                                                                let len = points.len();
+                                                                points.insert(j,(points[j-1] + points[j%len])/2.);
+                                                            }
+                                                        },
+                                                    }
+                                                });
                                                 door.wall = door.wall.modulo(num_walls as isize);
                                             }
                                         },
+                                        SelectedItem::Junction { junction_index } => {
+                                            let mut opening_to_remove=None;
+                                            if let Some(junction) = level.junctions.get_mut(*junction_index){
+                                                ui.collapsing("Position", |ui|{
+                                                    add_drag_value(ui,"X:",&mut junction.position.x,0.1);
+                                                    add_drag_value(ui,"Y:",&mut junction.position.y,0.1);
+                                                    add_drag_value(ui,"Z:",&mut junction.position.z,0.1);
+                                                    add_drag_value(ui,"Rot:",&mut junction.rotation.0,1.);
+                                                    add_drag_value(ui,"Height",&mut junction.height, 0.1);
+                                                });
+                                                ui.collapsing("Textures", |ui|{
+                                                    add_texture_controls(ui, "Floor texture",&mut junction.floor_texture);
+                                                    add_texture_controls(ui, "Roof texture",&mut junction.roof_texture);
+                                                    add_texture_controls(ui, "Wall texture",&mut junction.wall_texture);
+                                                });
+                                                ui.collapsing("Openings", |ui|{
+                                                    junction.openings.iter_mut().enumerate().for_each(|(i,opening)|{
+                                                        ui.collapsing(format!("Opening {}",i+1), |ui|{
+                                                            add_drag_value(ui, "Angle:", &mut opening.angle.0, 1.);
+                                                            add_drag_value(ui, "Distance:", &mut opening.distance, 0.1);
+                                                            add_drag_value(ui, "Width:", &mut opening.width, 0.1);
+                                                            if ui.button("−").clicked(){
+                                                                opening_to_remove = Some(i);
+                                                            }
+                                                        });
+                                                    });
+                                                    if ui.button("+").clicked(){
+                                                        let width = junction.openings.last().map_or(1., |opening|opening.width);
+                                                        junction.openings.push(JunctionOpening { angle: Deg(0.), distance: width/2., width });
+                                                    }
+                                                });
+                                            }
+                                            if let Some(i) = opening_to_remove{
+                                                level.junctions[*junction_index].openings.remove(i);
+                                                level.junction_opening_removed(*junction_index, i);
+                                            }
+                                        },
                                         SelectedItem::HallWay { hallway_index } => {
                                             fn add_control_rect_controls(ui:&mut Ui,name:impl Into<WidgetText>,control_rect:&mut ControlRect){
                                                 ui.collapsing(name, |ui|{
@@ -728,6 +1316,23 @@ impl ApplicationState {
                                                     });
                                                 });
                                             }
+                                            fn add_junction_selection(ui:&mut Ui,location:&mut DoorLocation,junctions:&[Junction]){
+                                                ComboBox::from_label("Junction").selected_text(
+                                                    match location.junction{
+                                                        Some((junction,opening)) if junctions.get(junction).is_some_and(|found|opening<found.openings.len()) => {
+                                                            format!("Junction {} Opening {}",junction+1,opening+1)
+                                                        },
+                                                        _ => "None".into(),
+                                                    }
+                                                ).show_ui(ui, |ui|{
+                                                    ui.selectable_value(&mut location.junction, None, "None");
+                                                    junctions.iter().enumerate().for_each(|(junction,found)|{
+                                                        (0..found.openings.len()).for_each(|opening|{
+                                                            ui.selectable_value(&mut location.junction, Some((junction,opening)), format!("Junction {} Opening {}",junction+1,opening+1));
+                                                        });
+                                                    });
+                                                });
+                                            }
                                             if let Some(hallway) = level.hallways.get_mut(*hallway_index){                                                
                                                 let add_hallway_texture_controls = |ui:&mut Ui,hallway_texs:&mut HallWayTexData|{
                                                     ui.collapsing("Textures", |ui|{
@@ -736,7 +1341,47 @@ impl ApplicationState {
                                                         add_texture_controls(ui,"Left",&mut hallway_texs.left);
                                                         add_texture_controls(ui,"Right",&mut hallway_texs.right);
                                                     });
+                                                    ui.horizontal(|ui|{
+                                                        let mut has_stairs = hallway_texs.stairs.is_some();
+                                                        ui.label("Stairs:");
+                                                        toggle_ui(ui, &mut has_stairs);
+                                                        if has_stairs != hallway_texs.stairs.is_some(){
+                                                            hallway_texs.stairs = has_stairs.then(||Stairs { steps: StepSize::Height(0.25), riser: hallway_texs.bottom.clone() });
+                                                        }
+                                                    });
+                                                    if let Some(stairs) = &mut hallway_texs.stairs{
+                                                        ui.collapsing("Stairs", |ui|{
+                                                            ui.horizontal(|ui|{
+                                                                let mut by_count = matches!(stairs.steps, StepSize::Count(_));
+                                                                ui.label("Step Height");
+                                                                toggle_ui(ui, &mut by_count);
+                                                                ui.label("Step Count");
+                                                                if by_count != matches!(stairs.steps, StepSize::Count(_)){
+                                                                    stairs.steps = if by_count {StepSize::Count(8)} else {StepSize::Height(0.25)};
+                                                                }
+                                                            });
+                                                            match &mut stairs.steps{
+                                                                StepSize::Count(count) => add_drag_value(ui, "Steps:", count, 0.1),
+                                                                StepSize::Height(height) => add_drag_value(ui, "Height:", height, 0.01),
+                                                            }
+                                                            add_texture_controls(ui,"Riser",&mut stairs.riser);
+                                                        });
+                                                    }
                                                 };
+                                                ui.collapsing("Path", |ui|{
+                                                    ComboBox::from_label("Path")
+                                                        .selected_text(match hallway.path{
+                                                            HallWayPath::Straight => "Straight",
+                                                            HallWayPath::Curved { .. } => "Curved",
+                                                        })
+                                                        .show_ui(ui, |ui|{
+                                                            ui.selectable_value(&mut hallway.path, HallWayPath::Straight, "Straight");
+                                                            ui.selectable_value(&mut hallway.path, HallWayPath::Curved { subdivisions: 8 }, "Curved");
+                                                        });
+                                                    if let HallWayPath::Curved { subdivisions } = &mut hallway.path{
+                                                        add_drag_value(ui, "Subdivisions:", subdivisions, 0.1);
+                                                    }
+                                                });
                                                 ui.collapsing("Start", |ui|{
                                                     ui.horizontal(|ui|{
                                                         ui.label("Snap to door");
@@ -787,6 +1432,7 @@ impl ApplicationState {
                                                                 }
                                                             }
                                                         });
+                                                        add_junction_selection(ui, &mut hallway.start_location, &level.junctions);
                                                     });
                                                     ui.add_enabled_ui(!hallway.start_location.enabled, |ui|{
                                                         add_control_rect_controls(ui, "Start", &mut hallway.start);
@@ -889,6 +1535,7 @@ impl ApplicationState {
                                                                 }
                                                             }
                                                         });
+                                                        add_junction_selection(ui, &mut hallway.end_location, &level.junctions);
                                                     });
                                                     ui.add_enabled_ui(!hallway.end_location.enabled, |ui|{
                                                         add_control_rect_controls(ui, "end", &mut hallway.end);
@@ -900,6 +1547,33 @@ impl ApplicationState {
                                 }
                             });
                         });
+                        let mut import_modal = Modal::new(ctx, "mesh import error");
+                        if let Some(dialog) = mesh_import_dialog{
+                            if dialog.show(ctx).selected(){
+                                if let Some(path) = dialog.path(){
+                                    match game_data.import_mesh(path){
+                                        Ok(name) => {
+                                            level.forget_static_mesh(&name);
+                                            if let Some(SelectedItem::Modifer { room_index, modifer_index }) = selected_item{
+                                                if let Some(Modifier::StaticMesh { file, .. }) = level.rooms.get_mut(room_index).and_then(|room|room.moddifiers.get_mut(*modifer_index)){
+                                                    *file = name;
+                                                }
+                                            }
+                                        },
+                                        Err(err) => {
+                                            import_modal
+                                                .dialog()
+                                                .with_title("Import Failed")
+                                                .with_body(format!("Couldn't import {}: {}",path.display(),err))
+                                                .open();
+                                        },
+                                    }
+                                }
+                                *mesh_import_dialog = None;
+                            }
+                        }
+                        import_modal.show_dialog();
+                        level.load_static_meshes(&game_data.meshes);
                     }
                 }
             },
@@ -927,7 +1601,7 @@ impl ApplicationState {
             if let ApplicationState{screen_state:ScreenState::Editor { editor_state,game_data,..},..} = self{
                 if let EditorState::LevelSelection { selected_level:Some(selected_level),.. } = editor_state.clone() {
                     let default_tex: MeshTex = MeshTex::new(self.default_tex.clone(), TileStyle::tile_scale(1., true));
-                    *editor_state = EditorState::LevelEditing { selected_level: selected_level.clone(),selected_item:None,new_moddifer:Modifier::Disc { pos: Vector3::new(0., 0., 0.), size: Vector3::new(1., 1., 1.), sides: vec![default_tex.clone(),default_tex.clone(),default_tex.clone(),default_tex.clone(),default_tex.clone()], dir: Deg(0.), top_tex: default_tex.clone(), bottom_tex: default_tex.clone() } };
+                    *editor_state = EditorState::LevelEditing { selected_level: selected_level.clone(),selected_item:None,new_moddifer:Modifier::Disc { pos: Vector3::new(0., 0., 0.), size: Vector3::new(1., 1., 1.), sides: vec![default_tex.clone(),default_tex.clone(),default_tex.clone(),default_tex.clone(),default_tex.clone()], dir: Deg(0.), top_tex: default_tex.clone(), bottom_tex: default_tex.clone(), top_size: None, bevel: None } };
                     self.level_state = game_data.levels_data[&selected_level].clone();
                 }
             }
@@ -954,6 +1628,11 @@ impl ApplicationState {
                 }
             },
             
+            WindowEvent::DroppedFile(path)=>{
+                if let ScreenState::Editor { game_data, .. } = &mut self.screen_state{
+                    import_texture(&self.platform.context(), &mut self.render_state, game_data, path);
+                }
+            }
             WindowEvent::MouseInput { state:ElementState::Pressed, button:MouseButton::Left,.. } if self.interacting_with_ui => {
                 if !is_event_captured {
                     self.interacting_with_ui = false;
@@ -1068,4 +1747,35 @@ fn add_drag_value<T>(ui:&mut Ui,name:&str, value:&mut T,speed:f64)where T:emath:
         ui.label(name);
         ui.add(DragValue::new(value).speed(speed))
     });
-}
\ No newline at end of file
+}
+fn open_save_error(ctx:&Context, folder_path:&Path, err:anyhow::Error){
+    Modal::new(ctx, "save error")
+        .dialog()
+        .with_title("Save Failed")
+        .with_body(format!("Couldn't save {}: {}\nThe folder on disk was left as it was.",folder_path.display(),err))
+        .open();
+}
+
+fn import_texture(ctx:&Context, render_state:&mut State, game_data:&mut GameData, file:&Path){
+    match game_data.import_texture(file){
+        Ok(id)=>upload_texture(ctx, render_state, game_data, &id),
+        Err(err)=>open_texture_error(ctx, "Import Failed", err.context(format!("couldn't import {}",file.display()))),
+    }
+}
+
+fn upload_texture(ctx:&Context, render_state:&mut State, game_data:&GameData, id:&TextureId){
+    if let Some((_,data,extension)) = game_data.textures.iter().find(|a|a.0 == *id){
+        //a replaced texture keeps its uri so egui would keep showing the old image
+        ctx.forget_image(&format!("bytes://{}.{}",id,extension));
+        let texture = render_state.create_texture(data.clone());
+        render_state.textures.insert(id.clone(), texture);
+    }
+}
+
+fn open_texture_error(ctx:&Context, title:&str, err:anyhow::Error){
+    Modal::new(ctx, "texture error")
+        .dialog()
+        .with_title(title)
+        .with_body(format!("{:#}",err))
+        .open();
+}
@@ -1,34 +1,139 @@
-use crate::{level::{level::LevelState, room::Chain}, renderer::texture::TextureId};
+use crate::{level::{level::LevelState, static_mesh::StaticMeshData}, renderer::texture::TextureId};
+use super::migration::{migrate_level, FORMAT_VERSION};
 use core::result::Result;
 use anyhow::Ok;
 use cfg_if::cfg_if;
-use itertools::Itertools;
-use ron::ser::PrettyConfig;
+use ron::{error::Position, ser::PrettyConfig};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap, ffi::OsStr, fs::{self, create_dir, read, read_dir, read_to_string}, ops::ControlFlow, path::{Path, PathBuf}, sync::Arc
+    collections::{HashMap, HashSet}, ffi::OsStr, fmt, fs::{self, create_dir, read, read_dir, read_to_string}, io::Cursor, path::{Path, PathBuf}, sync::Arc
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GameConfigFile {
+    //folders from before versioning don't have one
+    #[serde(default)]
+    pub version: u32,
     pub level_order: Vec<String>,
 }
 
 impl GameConfigFile {
     pub fn new() -> Self {
         Self {
+            version: FORMAT_VERSION,
             level_order: vec![],
         }
     }
 }
 
+/// How many earlier saves of a game folder are kept.
+pub const BACKUP_COUNT: usize = 3;
+
+/// `<folder>.<suffix>` next to the game folder at `path`.
+fn sibling_path(path: &Path, suffix: &str) -> anyhow::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or(anyhow::anyhow!("{} has no folder name", path.display()))?;
+    Result::Ok(path.with_file_name(format!("{}.{}", name, suffix)))
+}
+
+/// Moves the folder at `path` to `<folder>.backups/backup_1`, shifting older backups up and
+/// dropping the oldest. Empty folders aren't worth keeping and are just removed.
+fn backup_folder(path: &Path) -> anyhow::Result<Option<PathBuf>> {
+    if read_dir(path)?.next().is_none() {
+        fs::remove_dir(path)?;
+        return Ok(None);
+    }
+    let backups = sibling_path(path, "backups")?;
+    if !backups.exists() {
+        create_dir(&backups)?;
+    }
+    let backup = |i: usize| backups.join(format!("backup_{}", i));
+    if backup(BACKUP_COUNT).exists() {
+        fs::remove_dir_all(backup(BACKUP_COUNT))?;
+    }
+    (1..BACKUP_COUNT).rev().try_for_each(|i| {
+        if backup(i).exists() {
+            fs::rename(backup(i), backup(i + 1))?;
+        }
+        Ok(())
+    })?;
+    fs::rename(path, backup(1))?;
+    Ok(Some(backup(1)))
+}
+
+/// Finishes a save that stopped between moving the old folder away and moving the new one in,
+/// which leaves no folder at `path` but a complete one at `<folder>.saving`.
+fn recover_interrupted_save(path: &Path) -> anyhow::Result<()> {
+    let temp_path = sibling_path(path, "saving")?;
+    //config.ron is written last so an unfinished save doesn't have one
+    if !path.exists() && temp_path.join("config.ron").exists() {
+        fs::rename(temp_path, path)?;
+    }
+    Ok(())
+}
+
+/// Why part of a game folder couldn't be loaded.
+#[derive(Clone, Debug)]
+pub enum LoadError {
+    Folder {
+        path: PathBuf,
+        reason: String,
+    },
+    Config {
+        path: PathBuf,
+        position: Option<Position>,
+        reason: String,
+    },
+    Level {
+        name: String,
+        path: PathBuf,
+        position: Option<Position>,
+        reason: String,
+    },
+    Texture {
+        path: PathBuf,
+        reason: String,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = |path: &PathBuf, position: &Option<Position>| match position {
+            Some(position) => format!("{}:{}:{}", path.display(), position.line, position.col),
+            None => format!("{}", path.display()),
+        };
+        match self {
+            LoadError::Folder { path, reason } => write!(f, "folder {}: {}", path.display(), reason),
+            LoadError::Config { path, position, reason } => {
+                write!(f, "config {}: {}", location(path, position), reason)
+            }
+            LoadError::Level { name, path, position, reason } => {
+                write!(f, "level \"{}\" {}: {}", name, location(path, position), reason)
+            }
+            LoadError::Texture { path, reason } => write!(f, "texture {}: {}", path.display(), reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// A texture as stored in the game folder: its id, the raw file and the file extension.
+pub type TextureFile = (TextureId, Arc<[u8]>, Box<str>);
+
 #[derive(Clone, Debug)]
 pub struct GameData {
     pub config_file: GameConfigFile,
     pub levels: Vec<String>,
     pub levels_data: HashMap<String, LevelState>,
-    pub textures: Vec<(TextureId, Arc<[u8]>, Box<str>)>,
+    pub textures: Vec<TextureFile>,
+    pub meshes: Vec<(String, Arc<[u8]>)>,
     pub current_level: Option<String>,
+    /// Level and texture files that failed to load, kept as they were so saving doesn't lose them.
+    pub quarantined_files: Vec<(String, Arc<[u8]>)>,
+    /// Whether the folder was already backed up this session, later saves just replace it.
+    pub backed_up: bool,
 }
 
 impl GameData {
@@ -38,34 +143,62 @@ impl GameData {
             levels: vec![],
             levels_data: HashMap::new(),
             textures: vec![],
+            meshes: vec![],
             current_level: None,
+            quarantined_files: vec![],
+            backed_up: false,
         }
     }
     pub fn update_config(&mut self) {
+        self.config_file.version = FORMAT_VERSION;
         self.config_file.level_order = self.levels.clone();
     }
 
-    pub fn update_folder(&self, path: &PathBuf) -> anyhow::Result<()> {
-        fs::write(path.clone().chain(|a|{a.push("config.ron")}), ron::ser::to_string_pretty(&self.config_file,PrettyConfig::new())?)?;
-        if self.levels_data.iter().try_for_each(|(level_name,level)|{
-            match fs::write(path.clone().chain(|a|{a.push(format!("levels/{}.ron",level_name))}), match ron::ser::to_string_pretty(&level,PrettyConfig::new()){
-                Result::Ok(a) => a,
-                Err(_) => return ControlFlow::Break(()),
-            }){
-                Result::Ok(_)=>{},
-                Err(_)=>return ControlFlow::Break(())
-            };
-            return ControlFlow::Continue(());
-        }).is_break(){
-            return Ok(());
+    /// Saves the game folder to `path`. Everything is written to `<folder>.saving` first and
+    /// only swapped in once it's complete. On the first save of a session the folder it replaces
+    /// becomes the newest of the [`BACKUP_COUNT`] backups kept in `<folder>.backups`, later saves
+    /// move it to `<folder>.replaced` and delete it once the new one is in place.
+    ///
+    /// The swap is two renames, if the editor dies between them the folder is missing and
+    /// `<folder>.saving` holds the complete save. [`GameData::generate`] moves it back in place.
+    pub fn generate_new_game_folder(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        let temp_path = sibling_path(&path, "saving")?;
+        if temp_path.exists() {
+            fs::remove_dir_all(&temp_path)?;
+        }
+        fs::create_dir(&temp_path)?;
+        if let Err(err) = self.write_game_folder(&temp_path) {
+            let _ = fs::remove_dir_all(&temp_path);
+            return Err(err);
+        }
+        let old = if !path.exists() {
+            None
+        } else if self.backed_up {
+            let replaced = sibling_path(&path, "replaced")?;
+            if replaced.exists() {
+                fs::remove_dir_all(&replaced)?;
+            }
+            fs::rename(&path, &replaced)?;
+            Some(replaced)
+        } else {
+            backup_folder(&path)?
         };
+        if let Err(err) = fs::rename(&temp_path, &path) {
+            //put the old folder back so a failed swap leaves things as they were
+            if let Some(old) = old {
+                let _ = fs::rename(old, &path);
+            }
+            return Err(err.into());
+        }
+        if let (true, Some(replaced)) = (self.backed_up, old) {
+            let _ = fs::remove_dir_all(replaced);
+        }
+        self.backed_up = true;
         Ok(())
     }
 
-    pub fn generate_new_game_folder(&self, path: PathBuf) -> anyhow::Result<()> {
-        fs::remove_dir_all(&path).unwrap();
-        fs::create_dir(&path).unwrap();
-        let _ = create_dir(path.join("textures"));
+    fn write_game_folder(&self, path: &Path) -> anyhow::Result<()> {
+        create_dir(path.join("textures"))?;
         self.textures.iter().try_for_each(|texture| {
             fs::write(
                 path.join(format!("textures/{}.{}", texture.0, texture.2)),
@@ -73,7 +206,19 @@ impl GameData {
             )?;
             Ok(())
         })?;
-        let _ = create_dir(path.join("levels"));
+        create_dir(path.join("meshes"))?;
+        self.meshes.iter().try_for_each(|(name, data)| {
+            fs::write(path.join(format!("meshes/{}", name)), data.as_ref())?;
+            Ok(())
+        })?;
+        if !self.quarantined_files.is_empty() {
+            create_dir(path.join("quarantine"))?;
+            self.quarantined_files.iter().try_for_each(|(name, data)| {
+                fs::write(path.join(format!("quarantine/{}", name)), data.as_ref())?;
+                Ok(())
+            })?;
+        }
+        create_dir(path.join("levels"))?;
         self.levels.iter().try_for_each(|level| {
             fs::write(
                 path.join(format!("levels/{}.ron", level)),
@@ -91,63 +236,271 @@ impl GameData {
             path.join("config.ron"),
             ron::ser::to_string_pretty(&self.config_file, PrettyConfig::new())?.as_bytes(),
         )?;
-        //Self::generate(&path).expect("need to fix folder/file generating function");
         Ok(())
     }
-    pub fn generate(path: &PathBuf) -> Option<Self> {
-        let mut textures: Vec<(TextureId, Arc<[u8]>, Box<str>)> = vec![];
+    /// Loads the game folder at `path`. Only a missing folder or a broken config stops
+    /// loading, levels and textures that fail are left out and returned with the data.
+    pub fn generate(path: &Path) -> Result<(Self, Vec<LoadError>), LoadError> {
+        recover_interrupted_save(path).map_err(|err| LoadError::Folder {
+            path: path.to_path_buf(),
+            reason: err.to_string(),
+        })?;
+        let mut errors: Vec<LoadError> = vec![];
+        let mut textures: Vec<TextureFile> = vec![];
+        let mut quarantined_files: Vec<(String, Arc<[u8]>)> = vec![];
 
         read_dir(path.join("textures"))
-            .ok()?
-            .into_iter()
-            .for_each(|entry| {
-                if let Some(filepath) = entry.ok() {
-                    if filepath.path().has_extension(&["png", "jpg", "jpeg"]) {
-                        let texture_fullname = filepath
-                            .file_name()
-                            .into_string()
-                            .expect("non-unicode charecter in file name")
-                            .to_string();
-                        textures.push((
-                            texture_fullname.split(".").collect_vec()[0].into(),
-                            read(filepath.path())
-                                .expect("file can't be read for some reason :(")
-                                .into_boxed_slice()
-                                .into(),
-                            filepath
-                                .path()
-                                .extension()
-                                .expect("can't get file extension")
-                                .to_str()
-                                .expect("couldn't turn extextion into &str")
-                                .into(),
-                        ));
+            .map_err(|err| LoadError::Folder {
+                path: path.join("textures"),
+                reason: err.to_string(),
+            })?
+            .filter_map(|entry| entry.ok())
+            .for_each(|filepath| {
+                if filepath.path().has_extension(&["png", "jpg", "jpeg"]) {
+                    let texture_error = |reason| LoadError::Texture {
+                        path: filepath.path(),
+                        reason,
+                    };
+                    match read(filepath.path()) {
+                        Result::Ok(data) => match Self::read_texture(&filepath.path(), &data) {
+                            Result::Ok((name, extension)) => {
+                                textures.push((name, data.into_boxed_slice().into(), extension))
+                            }
+                            Err(reason) => {
+                                errors.push(texture_error(reason));
+                                quarantined_files.push((
+                                    filepath.file_name().to_string_lossy().into_owned(),
+                                    data.into_boxed_slice().into(),
+                                ));
+                            }
+                        },
+                        Err(err) => errors.push(texture_error(err.to_string())),
                     }
                 }
             });
-        let config_file: GameConfigFile =
-            ron::from_str(read_to_string(path.join("config.ron")).ok()?.as_str()).ok()?;
+        let mut meshes: Vec<(String, Arc<[u8]>)> = vec![];
+        if let Result::Ok(entries) = read_dir(path.join("meshes")) {
+            entries.filter_map(|entry| entry.ok()).for_each(|entry| {
+                if entry.path().has_extension(&StaticMeshData::EXTENSIONS) {
+                    if let (Some(name), Result::Ok(data)) =
+                        (entry.file_name().to_str(), read(entry.path()))
+                    {
+                        meshes.push((name.to_string(), data.into_boxed_slice().into()));
+                    }
+                }
+            });
+        }
+        let config_path = path.join("config.ron");
+        let mut config_file: GameConfigFile = ron::from_str(
+            read_to_string(&config_path)
+                .map_err(|err| LoadError::Config {
+                    path: config_path.clone(),
+                    position: None,
+                    reason: err.to_string(),
+                })?
+                .as_str(),
+        )
+        .map_err(|err| LoadError::Config {
+            path: config_path.clone(),
+            position: Some(err.position),
+            reason: err.code.to_string(),
+        })?;
+        //none of the levels could be read and saving would stamp an older version on the folder
+        if config_file.version > FORMAT_VERSION {
+            return Err(LoadError::Config {
+                path: config_path,
+                position: None,
+                reason: format!(
+                    "made by a newer editor (format version {}, this one reads up to {})",
+                    config_file.version, FORMAT_VERSION
+                ),
+            });
+        }
         let mut levels: Vec<String> = vec![];
         let mut levels_data: HashMap<String, LevelState> = HashMap::new();
         for level_name in &config_file.level_order {
-            levels.push(level_name.clone());
-            levels_data.insert(
-                level_name.clone(),
-                ron::from_str(
-                    read_to_string(path.join(format!("levels/{}.ron", level_name)))
-                        .ok()?
-                        .as_str(),
-                )
-                .ok()?,
-            );
-        }
-        Some(Self {
-            current_level: None,
-            config_file,
-            textures,
-            levels,
-            levels_data,
-        })
+            let level_path = path.join(format!("levels/{}.ron", level_name));
+            let level_error = |position, reason| LoadError::Level {
+                name: level_name.clone(),
+                path: level_path.clone(),
+                position,
+                reason,
+            };
+            let text = match read_to_string(&level_path) {
+                Result::Ok(text) => text,
+                Err(err) => {
+                    errors.push(level_error(None, err.to_string()));
+                    continue;
+                }
+            };
+            let level = migrate_level(config_file.version, text.clone())
+                .map_err(|err| level_error(None, err.to_string()))
+                .and_then(|migrated| {
+                    ron::from_str::<LevelState>(&migrated)
+                        .map_err(|err| level_error(Some(err.position), err.code.to_string()))
+                });
+            match level {
+                Result::Ok(level) => {
+                    levels.push(level_name.clone());
+                    levels_data.insert(level_name.clone(), level);
+                }
+                Err(err) => {
+                    errors.push(err);
+                    quarantined_files.push((format!("{}.ron", level_name), text.into_bytes().into()));
+                }
+            }
+        }
+        //files quarantined by earlier loads, the ones that just failed again replace them
+        if let Result::Ok(entries) = read_dir(path.join("quarantine")) {
+            entries.filter_map(|entry| entry.ok()).for_each(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if quarantined_files.iter().all(|(quarantined, _)| *quarantined != name) {
+                    if let Result::Ok(data) = read(entry.path()) {
+                        quarantined_files.push((name, data.into_boxed_slice().into()));
+                    }
+                }
+            });
+        }
+        //everything is upgraded in memory so the next save writes the newest version
+        config_file.version = FORMAT_VERSION;
+        levels_data
+            .values_mut()
+            .for_each(|level| level.load_static_meshes(&meshes));
+        Result::Ok((
+            Self {
+                current_level: None,
+                config_file,
+                textures,
+                meshes,
+                levels,
+                levels_data,
+                quarantined_files,
+                backed_up: false,
+            },
+            errors,
+        ))
+    }
+
+    fn read_texture(path: &Path, data: &[u8]) -> Result<(TextureId, Box<str>), String> {
+        let name = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .ok_or("non-unicode charecter in file name")?;
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .ok_or("can't get file extension")?;
+        image::io::Reader::new(Cursor::new(data))
+            .with_guessed_format()
+            .map_err(|err| err.to_string())?
+            .into_dimensions()
+            .map_err(|err| err.to_string())?;
+        Result::Ok((name.into(), extension.into()))
+    }
+
+    /// Registers the png/jpeg file at `file` as a texture, it's written to the game folder on the
+    /// next save. Names that are taken are refused, use [`GameData::replace_texture`] to swap
+    /// the image of an existing texture. Returns the new texture's id.
+    pub fn import_texture(&mut self, file: &Path) -> anyhow::Result<TextureId> {
+        if !file.has_extension(&["png", "jpg", "jpeg"]) {
+            return Err(anyhow::anyhow!("{} isn't a png or jpeg", file.display()));
+        }
+        let data = read(file)?;
+        let (name, extension) =
+            Self::read_texture(file, &data).map_err(|reason| anyhow::anyhow!(reason))?;
+        if &*name == "default" || self.textures.iter().any(|(texture_name, _, _)| *texture_name == name) {
+            return Err(anyhow::anyhow!("a texture called {} already exists", name));
+        }
+        //the renderer decodes the whole image so check all of it, not just the header
+        image::load_from_memory(&data)?;
+        self.textures
+            .push((name.clone(), data.into_boxed_slice().into(), extension));
+        Ok(name)
+    }
+
+    /// Renames a texture and points every level's references to it at the new name.
+    pub fn rename_texture(&mut self, id: &TextureId, new_id: TextureId) -> anyhow::Result<()> {
+        if new_id.is_empty() || new_id.contains(['/', '\\', '.']) {
+            return Err(anyhow::anyhow!("\"{}\" isn't a usable texture name", new_id));
+        }
+        if &*new_id == "default" || self.textures.iter().any(|(name, _, _)| *name == new_id) {
+            return Err(anyhow::anyhow!("a texture called {} already exists", new_id));
+        }
+        let texture = self
+            .textures
+            .iter_mut()
+            .find(|(name, _, _)| name == id)
+            .ok_or(anyhow::anyhow!("there is no texture called {}", id))?;
+        texture.0 = new_id.clone();
+        self.levels_data
+            .values_mut()
+            .flat_map(|level| level.mesh_texs_mut())
+            .filter(|mesh_tex| mesh_tex.id.id == *id)
+            .for_each(|mesh_tex| mesh_tex.id.id = new_id.clone());
+        Ok(())
+    }
+
+    /// Swaps the image of a texture for the png/jpeg at `file` and refreshes the aspect ratio
+    /// every level stored for it.
+    pub fn replace_texture(&mut self, id: &TextureId, file: &Path) -> anyhow::Result<()> {
+        if !file.has_extension(&["png", "jpg", "jpeg"]) {
+            return Err(anyhow::anyhow!("{} isn't a png or jpeg", file.display()));
+        }
+        let data = read(file)?;
+        let (_, extension) =
+            Self::read_texture(file, &data).map_err(|reason| anyhow::anyhow!(reason))?;
+        let image = image::load_from_memory(&data)?;
+        let ratio = image.width() as f32 / image.height() as f32;
+        let texture = self
+            .textures
+            .iter_mut()
+            .find(|(name, _, _)| name == id)
+            .ok_or(anyhow::anyhow!("there is no texture called {}", id))?;
+        *texture = (id.clone(), data.into_boxed_slice().into(), extension);
+        self.levels_data
+            .values_mut()
+            .flat_map(|level| level.mesh_texs_mut())
+            .filter(|mesh_tex| mesh_tex.id.id == *id)
+            .for_each(|mesh_tex| mesh_tex.id.ratio = ratio);
+        Ok(())
+    }
+
+    /// Textures that no level in the level order uses.
+    pub fn unused_textures(&self) -> Vec<TextureId> {
+        let used: HashSet<&TextureId> = self
+            .levels
+            .iter()
+            .filter_map(|level| self.levels_data.get(level))
+            .flat_map(|level| level.mesh_texs())
+            .map(|mesh_tex| &mesh_tex.id.id)
+            .collect();
+        self.textures
+            .iter()
+            .map(|(name, _, _)| name)
+            .filter(|name| !used.contains(name))
+            .cloned()
+            .collect()
+    }
+
+    /// Removes a texture, it's gone from disk on the next save.
+    pub fn delete_texture(&mut self, id: &TextureId) {
+        self.textures.retain(|(name, _, _)| name != id);
+    }
+
+    /// Registers the obj/gltf file at `file` so `Modifier::StaticMesh` can use it, it's written
+    /// to the game folder's `meshes/` directory on the next save. Returns the name it's stored under.
+    pub fn import_mesh(&mut self, file: &Path) -> anyhow::Result<String> {
+        let name = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(anyhow::anyhow!("{} has no usable file name", file.display()))?
+            .to_string();
+        let data: Arc<[u8]> = read(file)?.into_boxed_slice().into();
+        StaticMeshData::from_bytes(&name, &data)?;
+        self.meshes.retain(|(mesh_name, _)| *mesh_name != name);
+        self.meshes.push((name.clone(), data));
+        self.levels_data.values_mut().for_each(|level| level.forget_static_mesh(&name));
+        Ok(name)
     }
 }
 
@@ -208,3 +561,133 @@ pub async fn load_binary(file_name: &str) -> anyhow::Result<Vec<u8>> {
 
     Ok(data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        level::{
+            mesh::{MeshTex, TileStyle},
+            room::{Modifier, Room, RoomId},
+        },
+        renderer::texture::TextureData,
+        test_utils::temp_folder,
+    };
+
+    #[test]
+    fn rejects_a_folder_from_a_newer_editor() {
+        let path = temp_folder("newer_editor");
+        create_dir(path.join("textures")).unwrap();
+        fs::write(
+            path.join("config.ron"),
+            format!("(version: {}, level_order: [])", FORMAT_VERSION + 1),
+        )
+        .unwrap();
+        assert!(matches!(GameData::generate(&path), Err(LoadError::Config { .. })));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn quarantined_files_survive_saving_twice() {
+        let path = temp_folder("quarantine").join("game");
+        let mut game_data = GameData::new();
+        game_data.quarantined_files.push(("broken.ron".to_string(), b"(rooms:".to_vec().into()));
+        game_data.generate_new_game_folder(path.clone()).unwrap();
+        for _ in 0..2 {
+            let (mut game_data, errors) = GameData::generate(&path).unwrap();
+            assert!(errors.is_empty());
+            game_data.update_config();
+            game_data.generate_new_game_folder(path.clone()).unwrap();
+        }
+        assert_eq!(fs::read(path.join("quarantine/broken.ron")).unwrap(), b"(rooms:");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn only_the_first_save_of_a_session_is_backed_up() {
+        let path = temp_folder("backups").join("game");
+        GameData::new().generate_new_game_folder(path.clone()).unwrap();
+        let (mut game_data, _) = GameData::generate(&path).unwrap();
+        (0..3).for_each(|_| game_data.generate_new_game_folder(path.clone()).unwrap());
+        let backups = sibling_path(&path, "backups").unwrap();
+        assert!(backups.join("backup_1").exists());
+        assert!(!backups.join("backup_2").exists());
+        assert!(!sibling_path(&path, "replaced").unwrap().exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn recovers_a_save_interrupted_between_the_renames() {
+        let path = temp_folder("interrupted").join("game");
+        GameData::new().generate_new_game_folder(path.clone()).unwrap();
+        fs::rename(&path, sibling_path(&path, "saving").unwrap()).unwrap();
+        assert!(GameData::generate(&path).is_ok());
+        assert!(path.join("config.ron").exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn import_texture_refuses_taken_names() {
+        let path = temp_folder("import_texture");
+        let image = image::RgbaImage::new(2, 1);
+        ["brick.png", "default.png"]
+            .iter()
+            .for_each(|name| image.save(path.join(name)).unwrap());
+        let mut game_data = GameData::new();
+        assert_eq!(&*game_data.import_texture(&path.join("brick.png")).unwrap(), "brick");
+        assert!(game_data.import_texture(&path.join("brick.png")).is_err());
+        assert!(game_data.import_texture(&path.join("default.png")).is_err());
+        assert_eq!(game_data.textures.len(), 1);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn reimporting_a_mesh_replaces_the_loaded_geometry() {
+        let path = temp_folder("reimport_mesh");
+        let file = path.join("prop.obj");
+        let texture = MeshTex::new(
+            TextureData {
+                id: "default".into(),
+                ratio: 1.,
+            },
+            TileStyle::tile_scale(1., true),
+        );
+        let mut room = Room::new(
+            "room".into(),
+            [0., 0., 0.].into(),
+            cgmath::Deg(0.),
+            3.,
+            texture.clone(),
+            texture.clone(),
+            texture.clone(),
+        );
+        room.moddifiers.push(Modifier::StaticMesh {
+            file: "prop.obj".into(),
+            pos: [0., 0., 0.].into(),
+            dir: cgmath::Deg(0.),
+            scale: [1., 1., 1.].into(),
+            texture,
+            data: None,
+        });
+        let mut level = LevelState::none();
+        let id = RoomId::next_free(&level.rooms, &mut level.last_room_id);
+        level.rooms.insert(id, room);
+        let mut game_data = GameData::new();
+        game_data.levels_data.insert("level".into(), level);
+        let loaded_points = |game_data: &mut GameData| {
+            let level = game_data.levels_data.get_mut("level").unwrap();
+            level.load_static_meshes(&game_data.meshes);
+            match &level.rooms.values().next().unwrap().moddifiers[0] {
+                Modifier::StaticMesh { data, .. } => data.as_ref().unwrap().positions.len(),
+                _ => unreachable!(),
+            }
+        };
+        fs::write(&file, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        game_data.import_mesh(&file).unwrap();
+        assert_eq!(loaded_points(&mut game_data), 3);
+        fs::write(&file, "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+        game_data.import_mesh(&file).unwrap();
+        assert_eq!(loaded_points(&mut game_data), 4);
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::anyhow;
+
+/// Version written to `config.ron`, bump it and add a step to `LEVEL_MIGRATIONS`
+/// whenever a change to `LevelState` can't be read by the old files.
+pub const FORMAT_VERSION: u32 = 1;
+
+type Migration = fn(String) -> anyhow::Result<String>;
+
+/// `LEVEL_MIGRATIONS[n]` upgrades a level file from version `n` to `n + 1`.
+const LEVEL_MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [
+    //version 0 folders only miss the version in config.ron, the level files are the same
+    Ok,
+];
+
+/// Runs every migration step needed to bring a level file written with `version` up to
+/// `FORMAT_VERSION`.
+pub fn migrate_level(version: u32, level: String) -> anyhow::Result<String> {
+    if version > FORMAT_VERSION {
+        return Err(anyhow!(
+            "the folder was saved with format version {} but only versions up to {} can be opened",
+            version,
+            FORMAT_VERSION
+        ));
+    }
+    LEVEL_MIGRATIONS[version as usize..]
+        .iter()
+        .try_fold(level, |level, migration| migration(level))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_every_version_up_to_the_current_one() {
+        let level = "(rooms: {}, hallways: [])".to_string();
+        (0..=FORMAT_VERSION).for_each(|version| {
+            assert_eq!(migrate_level(version, level.clone()).unwrap(), level);
+        });
+    }
+
+    #[test]
+    fn rejects_a_future_version() {
+        assert!(migrate_level(FORMAT_VERSION + 1, String::new()).is_err());
+    }
+}
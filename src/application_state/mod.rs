@@ -1,3 +1,4 @@
 pub mod application_state;
 pub mod borrowed_toggle_switch;
 pub mod game_folder_structure;
+pub mod migration;
@@ -0,0 +1,246 @@
+use std::{env, path::PathBuf, process::ExitCode};
+
+use r3dget::{
+    application_state::game_folder_structure::GameData,
+    export::{export_level, ExportFormat},
+    level::{hallway::DoorLocation, level::LevelState},
+};
+
+const USAGE: &str = "usage: r3dget-cli <command> <game folder> [args]
+commands:
+    levels                          list the levels in play order
+    textures                        list the textures and their file extension
+    validate                        mesh every level and check hallway door links
+    resave                          rewrite the folder in canonical form
+    export <level> <output file>    export a level, .obj gives obj/mtl, anything else glTF";
+
+const EXIT_USAGE: u8 = 2;
+const EXIT_INVALID: u8 = 1;
+const EXIT_LOAD: u8 = 3;
+const EXIT_WRITE: u8 = 4;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (Some(command), Some(folder)) = (args.first(), args.get(1)) else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(EXIT_USAGE);
+    };
+    let folder_path = PathBuf::from(folder);
+    let (mut game_data, load_errors) = match GameData::generate(&folder_path) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("error\tload\t{err}");
+            return ExitCode::from(EXIT_LOAD);
+        }
+    };
+    //broken levels and textures are left out, the rest of the folder is still usable
+    load_errors
+        .iter()
+        .for_each(|err| eprintln!("warning\tload\t{err}"));
+    match (command.as_str(), &args[2..]) {
+        ("levels", []) => {
+            game_data
+                .levels
+                .iter()
+                .for_each(|level| println!("{level}"));
+            ExitCode::SUCCESS
+        }
+        ("textures", []) => {
+            game_data
+                .textures
+                .iter()
+                .for_each(|(name, _, extension)| println!("{name}\t{extension}"));
+            ExitCode::SUCCESS
+        }
+        ("validate", []) => {
+            let mut all_valid = load_errors.is_empty();
+            for (file, _) in game_data.quarantined_files.iter() {
+                println!("invalid\t{file}\tfailed to load");
+            }
+            for level_name in game_data.levels.iter() {
+                let errors = validate_level(&game_data.levels_data[level_name]);
+                if errors.is_empty() {
+                    println!("ok\t{level_name}");
+                }
+                errors.into_iter().for_each(|error| {
+                    all_valid = false;
+                    println!("invalid\t{level_name}\t{error}");
+                });
+            }
+            if all_valid {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::from(EXIT_INVALID)
+            }
+        }
+        ("resave", []) => {
+            game_data.update_config();
+            match game_data.generate_new_game_folder(folder_path.clone()) {
+                Ok(_) => {
+                    println!("saved\t{}", folder_path.display());
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("error\twrite\t{}\t{err}", folder_path.display());
+                    ExitCode::from(EXIT_WRITE)
+                }
+            }
+        }
+        ("export", [level_name, output]) => {
+            let Some(level) = game_data.levels_data.get(level_name) else {
+                eprintln!("error\tno level\t{level_name}");
+                return ExitCode::from(EXIT_USAGE);
+            };
+            let output = PathBuf::from(output);
+            let format = if output
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("obj"))
+            {
+                ExportFormat::Obj
+            } else {
+                ExportFormat::Gltf
+            };
+            match export_level(format, level, &game_data, &folder_path, &output) {
+                Ok(_) => {
+                    println!("exported\t{level_name}\t{}", output.display());
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("error\twrite\t{}\t{err}", output.display());
+                    ExitCode::from(EXIT_WRITE)
+                }
+            }
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(EXIT_USAGE)
+        }
+    }
+}
+
+fn validate_level(level: &LevelState) -> Vec<String> {
+    let mut errors = vec![];
+    level.rooms.values().for_each(|room| {
+        if let Err(err) = room.try_mesh() {
+            errors.push(format!("room \"{}\": {}", room.name, err));
+        }
+    });
+    level.hallways.iter().enumerate().for_each(|(i, hallway)| {
+        [
+            ("start", &hallway.start_location),
+            ("end", &hallway.end_location),
+        ]
+        .into_iter()
+        .for_each(|(end, location)| {
+            if let Some(error) = door_location_error(level, location) {
+                errors.push(format!("hallway {} {end}: {error}", i + 1));
+            }
+        });
+        if let Err(err) = hallway.try_mesh() {
+            errors.push(format!("hallway {}: {}", i + 1, err));
+        }
+    });
+    errors
+}
+
+fn door_location_error(level: &LevelState, location: &DoorLocation) -> Option<String> {
+    if !location.enabled {
+        return None;
+    }
+    if let Some((junction, opening)) = location.junction {
+        let Some(found) = level.junctions.get(junction) else {
+            return Some(format!("snaps to missing junction {}", junction + 1));
+        };
+        if opening >= found.openings.len() {
+            return Some(format!(
+                "snaps to missing opening {} of junction {}",
+                opening + 1,
+                junction + 1
+            ));
+        }
+        return None;
+    }
+    let Some(room_id) = location.room_index else {
+        return Some("enabled but not snapped to a room".into());
+    };
+    let Some(room) = level.rooms.get(&room_id) else {
+        return Some(format!("snaps to missing room {}", room_id.get()));
+    };
+    let Some(door_id) = location.door_id else {
+        return Some(format!("enabled but not snapped to a door in room \"{}\"", room.name));
+    };
+    if !room.doors.contains_key(&door_id) {
+        return Some(format!(
+            "snaps to missing door {} in room \"{}\"",
+            door_id.get(),
+            room.name
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use r3dget::renderer::texture::TextureData;
+
+    use super::*;
+
+    fn level() -> LevelState {
+        LevelState::new(&TextureData {
+            id: "default".into(),
+            ratio: 1.,
+        })
+    }
+
+    fn location(junction: Option<(usize, usize)>) -> DoorLocation {
+        DoorLocation {
+            room_index: level().rooms.keys().next().copied(),
+            door_id: None,
+            enabled: true,
+            junction,
+        }
+    }
+
+    #[test]
+    fn the_default_level_is_valid() {
+        assert!(validate_level(&level()).is_empty());
+    }
+
+    #[test]
+    fn disabled_locations_are_not_checked() {
+        let mut location = location(Some((3, 0)));
+        location.enabled = false;
+        assert_eq!(door_location_error(&level(), &location), None);
+    }
+
+    #[test]
+    fn missing_junctions_are_reported() {
+        assert_eq!(
+            door_location_error(&level(), &location(Some((3, 0)))),
+            Some("snaps to missing junction 4".to_string())
+        );
+    }
+
+    #[test]
+    fn unsnapped_locations_are_reported() {
+        let mut location = location(None);
+        assert_eq!(
+            door_location_error(&level(), &location),
+            Some("enabled but not snapped to a door in room \"default_room\"".to_string())
+        );
+        location.room_index = None;
+        assert_eq!(
+            door_location_error(&level(), &location),
+            Some("enabled but not snapped to a room".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_rooms_are_reported() {
+        let mut level = level();
+        let location = location(None);
+        level.rooms.clear();
+        assert!(door_location_error(&level, &location)
+            .is_some_and(|error| error.starts_with("snaps to missing room")));
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use gltf::json::{
+    self,
+    validation::{Checked::Valid, USize64},
+    Index,
+};
+use itertools::Itertools;
+
+use crate::{
+    application_state::game_folder_structure::TextureFile,
+    level::{
+        level::LevelState,
+        mesh::{Mesh, Meshable},
+    },
+    renderer::texture::TextureId,
+};
+
+const DEFAULT_TEXTURE: &[u8] = include_bytes!("../renderer/default.png");
+
+/// Writes `level` as glTF 2.0. A `.gltf` path gets a json file plus a `.bin` next to it,
+/// anything else is written as a single binary `.glb`. Textures are always embedded in the buffer.
+pub fn export_gltf(
+    level: &LevelState,
+    textures: &[TextureFile],
+    path: &Path,
+) -> anyhow::Result<()> {
+    let mut builder = GltfBuilder::new(textures);
+    level
+        .rooms
+        .values()
+        .sorted_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        .for_each(|room| builder.add_node(room.name.clone(), room.mesh()));
+    level
+        .hallways
+        .iter()
+        .enumerate()
+        .for_each(|(i, hallway)| builder.add_node(format!("Hallway {}", i + 1), hallway.mesh()));
+    level.junctions.iter().enumerate().for_each(|(i, junction)| {
+        builder.add_node(format!("Junction {}", i + 1), junction.mesh())
+    });
+
+    let is_gltf = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gltf"));
+    let bin_path = path.with_extension("bin");
+    let (root, bin) = builder.finish(if is_gltf {
+        bin_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.to_string())
+    } else {
+        None
+    });
+
+    if is_gltf {
+        fs::write(path, json::serialize::to_string_pretty(&root)?)?;
+        fs::write(bin_path, bin)?;
+    } else {
+        let mut json = json::serialize::to_string(&root)?.into_bytes();
+        pad_to_4(&mut json, b' ');
+        let glb = gltf::binary::Glb {
+            header: gltf::binary::Header {
+                magic: *b"glTF",
+                version: 2,
+                length: (12 + 8 + json.len() + 8 + bin.len()).try_into()?,
+            },
+            json: Cow::Owned(json),
+            bin: Some(Cow::Owned(bin)),
+        };
+        glb.to_writer(fs::File::create(path)?)?;
+    }
+    Ok(())
+}
+
+struct GltfBuilder<'a> {
+    textures: &'a [TextureFile],
+    root: json::Root,
+    bin: Vec<u8>,
+    materials: HashMap<TextureId, Index<json::Material>>,
+}
+
+impl<'a> GltfBuilder<'a> {
+    fn new(textures: &'a [TextureFile]) -> Self {
+        let mut root = json::Root::default();
+        root.samplers.push(json::texture::Sampler {
+            mag_filter: Some(Valid(json::texture::MagFilter::Linear)),
+            min_filter: Some(Valid(json::texture::MinFilter::Nearest)),
+            ..Default::default()
+        });
+        Self {
+            textures,
+            root,
+            bin: vec![],
+            materials: HashMap::new(),
+        }
+    }
+
+    fn push_view(
+        &mut self,
+        data: &[u8],
+        target: Option<json::buffer::Target>,
+    ) -> Index<json::buffer::View> {
+        pad_to_4(&mut self.bin, 0);
+        let view = json::buffer::View {
+            buffer: Index::new(0),
+            byte_length: USize64::from(data.len()),
+            byte_offset: Some(USize64::from(self.bin.len())),
+            byte_stride: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: None,
+            target: target.map(Valid),
+        };
+        self.bin.extend_from_slice(data);
+        self.root.buffer_views.push(view);
+        Index::new(self.root.buffer_views.len() as u32 - 1)
+    }
+
+    fn push_accessor(
+        &mut self,
+        data: &[u8],
+        count: usize,
+        component_type: json::accessor::ComponentType,
+        type_: json::accessor::Type,
+        bounds: Option<([f32; 3], [f32; 3])>,
+        target: json::buffer::Target,
+    ) -> Index<json::Accessor> {
+        let buffer_view = self.push_view(data, Some(target));
+        self.root.accessors.push(json::Accessor {
+            buffer_view: Some(buffer_view),
+            byte_offset: Some(USize64(0)),
+            count: USize64::from(count),
+            component_type: Valid(json::accessor::GenericComponentType(component_type)),
+            extensions: Default::default(),
+            extras: Default::default(),
+            type_: Valid(type_),
+            min: bounds.map(|(min, _)| json::Value::from(min.to_vec())),
+            max: bounds.map(|(_, max)| json::Value::from(max.to_vec())),
+            name: None,
+            normalized: false,
+            sparse: None,
+        });
+        Index::new(self.root.accessors.len() as u32 - 1)
+    }
+
+    fn material(&mut self, texture: &TextureId) -> Index<json::Material> {
+        if let Some(material) = self.materials.get(texture) {
+            return *material;
+        }
+        let textures = self.textures;
+        let (data, mime_type): (&[u8], &str) =
+            match textures.iter().find(|(name, _, _)| name == texture) {
+                Some((_, data, extension)) => (
+                    data.as_ref(),
+                    if extension.eq_ignore_ascii_case("png") {
+                        "image/png"
+                    } else {
+                        "image/jpeg"
+                    },
+                ),
+                None => (DEFAULT_TEXTURE, "image/png"),
+            };
+        let buffer_view = self.push_view(data, None);
+        self.root.images.push(json::Image {
+            buffer_view: Some(buffer_view),
+            mime_type: Some(json::image::MimeType(mime_type.into())),
+            name: Some(texture.to_string()),
+            uri: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+        });
+        self.root.textures.push(json::Texture {
+            name: Some(texture.to_string()),
+            sampler: Some(Index::new(0)),
+            source: Index::new(self.root.images.len() as u32 - 1),
+            extensions: Default::default(),
+            extras: Default::default(),
+        });
+        self.root.materials.push(json::Material {
+            name: Some(texture.to_string()),
+            pbr_metallic_roughness: json::material::PbrMetallicRoughness {
+                base_color_texture: Some(json::texture::Info {
+                    index: Index::new(self.root.textures.len() as u32 - 1),
+                    tex_coord: 0,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                }),
+                metallic_factor: json::material::StrengthFactor(0.),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let material = Index::new(self.root.materials.len() as u32 - 1);
+        self.materials.insert(texture.clone(), material);
+        material
+    }
+
+    fn add_node(&mut self, name: String, meshs: Vec<Mesh>) {
+        let mut by_texture: Vec<Mesh> = vec![];
+        meshs.into_iter().for_each(|mesh| {
+            if let Some(existing) = by_texture.iter_mut().find(|a| a.textrure == mesh.textrure) {
+                existing.combine(mesh);
+            } else {
+                by_texture.push(mesh);
+            }
+        });
+        let primitives = by_texture
+            .into_iter()
+            .filter(|mesh| !mesh.indices.is_empty())
+            .map(|mesh| {
+                let bounds = mesh.vertices.iter().fold(
+                    ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+                    |(mut min, mut max), vertex| {
+                        (0..3).for_each(|i| {
+                            min[i] = min[i].min(vertex.position[i]);
+                            max[i] = max[i].max(vertex.position[i]);
+                        });
+                        (min, max)
+                    },
+                );
+                let positions = mesh
+                    .vertices
+                    .iter()
+                    .flat_map(|vertex| vertex.position)
+                    .flat_map(f32::to_le_bytes)
+                    .collect_vec();
+                let tex_coords = mesh
+                    .vertices
+                    .iter()
+                    .flat_map(|vertex| vertex.tex_coords)
+                    .flat_map(f32::to_le_bytes)
+                    .collect_vec();
+                let normals = mesh
+                    .vertices
+                    .iter()
+                    .flat_map(|vertex| vertex.normal)
+                    .flat_map(f32::to_le_bytes)
+                    .collect_vec();
+                let tangents = mesh
+                    .vertices
+                    .iter()
+                    .flat_map(|vertex| vertex.tangent)
+                    .flat_map(f32::to_le_bytes)
+                    .collect_vec();
+                let indices = mesh
+                    .indices
+                    .iter()
+                    .flat_map(|index| index.to_le_bytes())
+                    .collect_vec();
+                let positions = self.push_accessor(
+                    &positions,
+                    mesh.vertices.len(),
+                    json::accessor::ComponentType::F32,
+                    json::accessor::Type::Vec3,
+                    Some(bounds),
+                    json::buffer::Target::ArrayBuffer,
+                );
+                let tex_coords = self.push_accessor(
+                    &tex_coords,
+                    mesh.vertices.len(),
+                    json::accessor::ComponentType::F32,
+                    json::accessor::Type::Vec2,
+                    None,
+                    json::buffer::Target::ArrayBuffer,
+                );
+                let normals = self.push_accessor(
+                    &normals,
+                    mesh.vertices.len(),
+                    json::accessor::ComponentType::F32,
+                    json::accessor::Type::Vec3,
+                    None,
+                    json::buffer::Target::ArrayBuffer,
+                );
+                let tangents = self.push_accessor(
+                    &tangents,
+                    mesh.vertices.len(),
+                    json::accessor::ComponentType::F32,
+                    json::accessor::Type::Vec4,
+                    None,
+                    json::buffer::Target::ArrayBuffer,
+                );
+                let indices = self.push_accessor(
+                    &indices,
+                    mesh.indices.len(),
+                    json::accessor::ComponentType::U32,
+                    json::accessor::Type::Scalar,
+                    None,
+                    json::buffer::Target::ElementArrayBuffer,
+                );
+                json::mesh::Primitive {
+                    attributes: BTreeMap::from([
+                        (Valid(json::mesh::Semantic::Positions), positions),
+                        (Valid(json::mesh::Semantic::TexCoords(0)), tex_coords),
+                        (Valid(json::mesh::Semantic::Normals), normals),
+                        (Valid(json::mesh::Semantic::Tangents), tangents),
+                    ]),
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                    indices: Some(indices),
+                    material: Some(self.material(&mesh.textrure)),
+                    mode: Valid(json::mesh::Mode::Triangles),
+                    targets: None,
+                }
+            })
+            .collect_vec();
+        let mesh = if primitives.is_empty() {
+            None
+        } else {
+            self.root.meshes.push(json::Mesh {
+                extensions: Default::default(),
+                extras: Default::default(),
+                name: Some(name.clone()),
+                primitives,
+                weights: None,
+            });
+            Some(Index::new(self.root.meshes.len() as u32 - 1))
+        };
+        self.root.nodes.push(json::Node {
+            camera: None,
+            children: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+            matrix: None,
+            mesh,
+            name: Some(name),
+            rotation: None,
+            scale: None,
+            translation: None,
+            skin: None,
+            weights: None,
+        });
+    }
+
+    fn finish(mut self, bin_uri: Option<String>) -> (json::Root, Vec<u8>) {
+        pad_to_4(&mut self.bin, 0);
+        self.root.buffers.push(json::Buffer {
+            byte_length: USize64::from(self.bin.len()),
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: None,
+            uri: bin_uri,
+        });
+        self.root.scenes.push(json::Scene {
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: None,
+            nodes: (0..self.root.nodes.len() as u32)
+                .map(Index::new)
+                .collect_vec(),
+        });
+        self.root.scene = Some(Index::new(0));
+        (self.root, self.bin)
+    }
+}
+
+/// glTF wants every chunk and buffer view to start on a multiple of 4 bytes.
+fn pad_to_4(bytes: &mut Vec<u8>, padding: u8) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(padding);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gltf::mesh::Mode;
+
+    use super::*;
+    use crate::{
+        export::tests::{level, triangle_count},
+        test_utils::temp_folder,
+    };
+
+    /// Exports the test level to `file` and reads it back with the `gltf` crate.
+    fn round_trip(file: &str) {
+        let (level, textures) = level();
+        let folder = temp_folder(file);
+        let path = folder.join(file);
+        export_gltf(&level, &textures, &path).unwrap();
+        let (document, buffers, images) = gltf::import(&path).unwrap();
+
+        let names = document
+            .nodes()
+            .filter_map(|node| node.name())
+            .sorted()
+            .collect_vec();
+        assert_eq!(names, ["Hallway 1", "Room"]);
+        let mut triangles = 0;
+        document
+            .meshes()
+            .flat_map(|mesh| mesh.primitives())
+            .for_each(|primitive| {
+                assert_eq!(primitive.mode(), Mode::Triangles);
+                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+                let vertices = reader.read_positions().unwrap().count();
+                assert_eq!(reader.read_normals().unwrap().count(), vertices);
+                assert_eq!(
+                    reader.read_tex_coords(0).unwrap().into_f32().count(),
+                    vertices
+                );
+                let indices = reader.read_indices().unwrap().into_u32().collect_vec();
+                assert!(indices.iter().all(|index| (*index as usize) < vertices));
+                triangles += indices.len() / 3;
+            });
+        assert_eq!(triangles, triangle_count(&level));
+
+        let materials = document
+            .materials()
+            .filter_map(|material| material.name())
+            .sorted()
+            .collect_vec();
+        assert_eq!(materials, ["brick", "default"]);
+        let brick = document
+            .images()
+            .position(|image| image.name() == Some("brick"))
+            .unwrap();
+        assert_eq!((images[brick].width, images[brick].height), (2, 1));
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn glb_round_trip() {
+        round_trip("level.glb");
+    }
+
+    #[test]
+    fn gltf_round_trip() {
+        round_trip("level.gltf");
+    }
+}
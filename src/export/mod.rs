@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use crate::{application_state::game_folder_structure::GameData, level::level::LevelState};
+
+pub mod gltf_export;
+pub mod obj_export;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Gltf,
+    Obj,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Gltf => "glb",
+            ExportFormat::Obj => "obj",
+        }
+    }
+}
+
+/// Exports `level` using the textures of the game folder at `folder_path`.
+pub fn export_level(
+    format: ExportFormat,
+    level: &LevelState,
+    game_data: &GameData,
+    folder_path: &Path,
+    path: &Path,
+) -> anyhow::Result<()> {
+    match format {
+        ExportFormat::Gltf => gltf_export::export_gltf(level, &game_data.textures, path),
+        ExportFormat::Obj => obj_export::export_obj(
+            level,
+            &game_data.textures,
+            &folder_path.join("textures"),
+            path,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use cgmath::{Deg, Vector2, Vector3};
+
+    use crate::{
+        application_state::game_folder_structure::TextureFile,
+        level::{
+            hallway::{ControlRect, HallWay, HallWayTexData},
+            mesh::{MeshTex, Meshable, TileStyle},
+            room::{Room, RoomId},
+        },
+        renderer::texture::TextureData,
+    };
+
+    use super::*;
+
+    fn texture(id: &str) -> MeshTex {
+        MeshTex::new(
+            TextureData {
+                id: id.into(),
+                ratio: 2.,
+            },
+            TileStyle::tile_scale(1., true),
+        )
+    }
+
+    /// A room textured with "brick", which is in the returned textures, and a hallway using
+    /// "default", which isn't.
+    pub(super) fn level() -> (LevelState, Vec<TextureFile>) {
+        let mut level = LevelState::none();
+        let brick = texture("brick");
+        let room = Room::new(
+            "Room".into(),
+            Vector3::new(0., 0., 0.),
+            Deg(0.),
+            3.,
+            brick.clone(),
+            brick.clone(),
+            brick,
+        );
+        let id = RoomId::next_free(&level.rooms, &mut level.last_room_id);
+        level.rooms.insert(id, room);
+        level.hallways.push(HallWay::new(
+            ControlRect::new(Vector3::new(0., 0., 2.), Deg(0.), Vector2::new(1., 2.)),
+            ControlRect::new(Vector3::new(0., 0., 6.), Deg(0.), Vector2::new(1., 2.)),
+            HallWayTexData::all(texture("default")),
+        ));
+        let mut png = Cursor::new(vec![]);
+        image::RgbaImage::new(2, 1)
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+        let textures = vec![("brick".into(), png.into_inner().into(), "png".into())];
+        (level, textures)
+    }
+
+    /// Triangles the level is made of, to check an export against.
+    pub(super) fn triangle_count(level: &LevelState) -> usize {
+        level.mesh().iter().map(|mesh| mesh.indices.len() / 3).sum()
+    }
+}
@@ -0,0 +1,191 @@
+use std::{fmt::Write, fs, path::Path};
+
+use itertools::Itertools;
+
+use crate::{
+    application_state::game_folder_structure::TextureFile,
+    level::{
+        level::LevelState,
+        mesh::{Mesh, Meshable},
+    },
+    renderer::texture::TextureId,
+};
+
+/// Writes `level` as a Wavefront obj with a matching mtl next to it. Every room and hallway
+/// becomes a group and faces are split by texture, with `map_Kd` pointing into `textures_folder`.
+pub fn export_obj(
+    level: &LevelState,
+    textures: &[TextureFile],
+    textures_folder: &Path,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let mut obj = String::new();
+    let mut mtl = String::new();
+    let mut materials: Vec<TextureId> = vec![];
+    let mut vertex_count = 0;
+
+    writeln!(
+        obj,
+        "mtllib {}",
+        mtl_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("level.mtl")
+    )?;
+    let groups = level
+        .rooms
+        .values()
+        .sorted_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        .map(|room| (room.name.clone(), room.mesh()))
+        .chain(
+            level
+                .hallways
+                .iter()
+                .enumerate()
+                .map(|(i, hallway)| (format!("Hallway {}", i + 1), hallway.mesh())),
+        )
+        .chain(
+            level
+                .junctions
+                .iter()
+                .enumerate()
+                .map(|(i, junction)| (format!("Junction {}", i + 1), junction.mesh())),
+        )
+        .collect_vec();
+    for (name, meshs) in groups {
+        writeln!(obj, "g {}", group_name(&name))?;
+        let meshs: Vec<Mesh> = meshs
+            .into_iter()
+            .sorted_by(|a, b| a.textrure.cmp(&b.textrure))
+            .collect_vec();
+        for mesh in meshs.iter() {
+            for vertex in mesh.vertices.iter() {
+                writeln!(
+                    obj,
+                    "v {} {} {}",
+                    vertex.position[0], vertex.position[1], vertex.position[2]
+                )?;
+                writeln!(
+                    obj,
+                    "vt {} {}",
+                    vertex.tex_coords[0],
+                    1. - vertex.tex_coords[1]
+                )?;
+                writeln!(
+                    obj,
+                    "vn {} {} {}",
+                    vertex.normal[0], vertex.normal[1], vertex.normal[2]
+                )?;
+            }
+        }
+        let mut current_material: Option<&TextureId> = None;
+        for mesh in meshs.iter() {
+            if current_material != Some(&mesh.textrure) {
+                writeln!(obj, "usemtl {}", group_name(&mesh.textrure))?;
+                current_material = Some(&mesh.textrure);
+            }
+            if !materials.contains(&mesh.textrure) {
+                materials.push(mesh.textrure.clone());
+            }
+            for (a, b, c) in mesh.indices.iter().tuples() {
+                let (a, b, c) = (
+                    *a as usize + vertex_count + 1,
+                    *b as usize + vertex_count + 1,
+                    *c as usize + vertex_count + 1,
+                );
+                writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+            }
+            vertex_count += mesh.vertices.len();
+        }
+    }
+
+    for material in materials.iter() {
+        writeln!(mtl, "newmtl {}", group_name(material))?;
+        writeln!(mtl, "Ka 1.0 1.0 1.0")?;
+        writeln!(mtl, "Kd 1.0 1.0 1.0")?;
+        writeln!(mtl, "Ks 0.0 0.0 0.0")?;
+        writeln!(mtl, "illum 1")?;
+        if let Some((name, _, extension)) = textures.iter().find(|(name, _, _)| name == material) {
+            writeln!(
+                mtl,
+                "map_Kd {}",
+                textures_folder
+                    .join(format!("{}.{}", name, extension))
+                    .display()
+            )?;
+        }
+        writeln!(mtl)?;
+    }
+
+    fs::write(path, obj)?;
+    fs::write(mtl_path, mtl)?;
+    Ok(())
+}
+
+/// obj and mtl names end at the first whitespace
+fn group_name(name: &str) -> String {
+    name.split_whitespace().join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        export::tests::{level, triangle_count},
+        test_utils::temp_folder,
+    };
+
+    #[test]
+    fn obj_round_trip() {
+        let (level, textures) = level();
+        let folder = temp_folder("obj");
+        let path = folder.join("level.obj");
+        export_obj(&level, &textures, &folder.join("textures"), &path).unwrap();
+        let (models, materials) = tobj::load_obj(
+            &path,
+            &tobj::LoadOptions {
+                single_index: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let materials = materials.unwrap();
+
+        let names = models
+            .iter()
+            .map(|model| model.name.as_str())
+            .unique()
+            .sorted()
+            .collect_vec();
+        assert_eq!(names, ["Hallway_1", "Room"]);
+        let triangles: usize = models
+            .iter()
+            .map(|model| model.mesh.indices.len() / 3)
+            .sum();
+        assert_eq!(triangles, triangle_count(&level));
+        models.iter().for_each(|model| {
+            let vertices = model.mesh.positions.len() / 3;
+            assert_eq!(model.mesh.normals.len() / 3, vertices);
+            assert_eq!(model.mesh.texcoords.len() / 2, vertices);
+            assert!(model.mesh.material_id.is_some());
+        });
+
+        let brick = materials
+            .iter()
+            .find(|material| material.name == "brick")
+            .unwrap();
+        assert_eq!(
+            PathBuf::from(&brick.diffuse_texture),
+            folder.join("textures").join("brick.png")
+        );
+        let default = materials
+            .iter()
+            .find(|material| material.name == "default")
+            .unwrap();
+        assert!(default.diffuse_texture.is_empty());
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -1,13 +1,16 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 use crate::level::mesh::MeshTex;
 
-use cgmath::{Array, Basis2, MetricSpace, Deg, Rotation, Rotation2, Vector2, Vector3};
+use cgmath::{
+    Angle, Array, Basis2, Deg, InnerSpace, MetricSpace, Rad, Rotation, Rotation2, Vector2,
+    Vector3, VectorSpace,
+};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    mesh::{Mesh, MeshVertex, Meshable},
+    mesh::{quad, Mesh, MeshVertex, Meshable},
     room::{DoorId, Room, RoomId},
 };
 
@@ -19,6 +22,18 @@ pub struct HallWay {
     pub end: ControlRect,
     pub start_location: DoorLocation,
     pub end_location: DoorLocation,
+    #[serde(default)]
+    pub path: HallWayPath,
+}
+
+/// How a hallway gets from one control rect to the next.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub enum HallWayPath {
+    #[default]
+    Straight,
+    /// A smooth curve leaving every control rect straight out of it, split into
+    /// `subdivisions` straight pieces between each pair of control rects.
+    Curved { subdivisions: u16 },
 }
 
 impl HallWay {
@@ -32,58 +47,121 @@ impl HallWay {
                 room_index: None,
                 door_id: None,
                 enabled: false,
+                junction: None,
             },
             end_location: DoorLocation {
                 room_index: None,
                 door_id: None,
                 enabled: false,
+                junction: None,
             },
+            path: HallWayPath::Straight,
         }
     }
-    pub fn update_door_location(&mut self, rooms: &HashMap<RoomId, Room>) {
-        if self.start_location.enabled {
-            if let Some(room_index) = self.start_location.room_index {
-                if let Some(door_id) = self.start_location.door_id {
-                    if let Some(room) = rooms.get(&room_index){                        
-                        if let Some(c_rect) = room.get_control_rect(&door_id, false) {
-                            self.start = c_rect;
-                        }
-                    }
-                }
-            }
+    pub fn update_door_location(&mut self, rooms: &HashMap<RoomId, Room>, junctions: &[Junction]) {
+        if let Some(c_rect) = self.start_location.control_rect(rooms, junctions, false) {
+            self.start = c_rect;
         }
-        if self.end_location.enabled {
-            if let Some(room_index) = self.end_location.room_index {
-                if let Some(door_id) = self.end_location.door_id {
-                    if let Some(room) = rooms.get(&room_index){                        
-                        if let Some(c_rect) = room.get_control_rect(&door_id, true) {
-                            self.end = c_rect;
-                        }
-                    }
-                }
-            }
+        if let Some(c_rect) = self.end_location.control_rect(rooms, junctions, true) {
+            self.end = c_rect;
         }
     }
 }
 
-impl Meshable for HallWay {
-    fn mesh(&self) -> Vec<Mesh> {
+impl HallWay {
+    /// Like [`Meshable::mesh`] but gives back why the hallway can't be meshed instead of an
+    /// empty mesh.
+    pub fn try_mesh(&self) -> Result<Vec<Mesh>, HallWayError> {
         let mut meshs = vec![];
         let mut start_c_rect = &self.start;
+        //how far along the left and right walls the last piece ended, so textures carry on
+        let mut along = (0., 0.);
         for i in 0..=self.middle.len() {
             let end_c_rect = self.middle.get(i).map(|t| &t.0).unwrap_or(&(self.end));
-            let start_texture = self
-                .middle
-                .get(i - 1)
+            let start_texture = i
+                .checked_sub(1)
+                .and_then(|i| self.middle.get(i))
                 .map(|t| &t.1)
                 .unwrap_or(&(self.start_texture));
 
-            let mut hallway_mesh = start_c_rect.gen_mesh(end_c_rect, start_texture);
-            meshs.append(&mut hallway_mesh);
+            match self.path {
+                HallWayPath::Straight => {
+                    meshs.append(&mut start_c_rect.gen_mesh(end_c_rect, start_texture)?);
+                }
+                HallWayPath::Curved { subdivisions } => {
+                    let mut pieces = start_c_rect.curve_to(end_c_rect, subdivisions.max(1));
+                    //the steps are counted for the whole curve and shared out between the
+                    //pieces, which are moved to the height of the step they start on
+                    let mut piece_steps = vec![];
+                    if let Some(stairs) = &start_texture.stairs {
+                        let (bottom, rise) = (
+                            start_c_rect.position.y,
+                            end_c_rect.position.y - start_c_rect.position.y,
+                        );
+                        let count = stairs.step_count(rise);
+                        if count > MAX_STEPS {
+                            return Err(HallWayError::TooManySteps(count));
+                        }
+                        let last = pieces.len() - 1;
+                        let steps_before = |piece: usize| (piece * count + last / 2) / last;
+                        if count > 0 {
+                            pieces.iter_mut().enumerate().for_each(|(i, piece)| {
+                                piece.position.y =
+                                    bottom + rise * steps_before(i) as f32 / count as f32;
+                            });
+                        }
+                        piece_steps = (0..last)
+                            .map(|i| steps_before(i + 1) - steps_before(i))
+                            .collect_vec();
+                    }
+                    pieces.iter().tuple_windows().enumerate().try_for_each(|(i, (a, b))| {
+                        let mut piece_texture = start_texture.clone();
+                        if let Some(stairs) = &mut piece_texture.stairs {
+                            stairs.steps = StepSize::Count(piece_steps[i] as u16);
+                        }
+                        let mut piece_mesh = a.gen_mesh_along(b, &piece_texture, along)?;
+                        along = (along.0 + a.left_distance(b), along.1 + a.right_distance(b));
+                        meshs.append(&mut piece_mesh);
+                        Ok(())
+                    })?;
+                }
+            }
             start_c_rect = end_c_rect
         }
 
-        meshs
+        Ok(meshs)
+    }
+}
+
+/// Most steps a single flight of stairs can have, the same as [`StepSize::Count`] can ask for.
+pub const MAX_STEPS: usize = u16::MAX as usize;
+
+/// Why a hallway can't be meshed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HallWayError {
+    /// A control rect's position, rotation or size isn't a finite number.
+    NotFinite,
+    /// A flight of stairs needs this many steps, more than [`MAX_STEPS`].
+    TooManySteps(usize),
+}
+
+impl fmt::Display for HallWayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HallWayError::NotFinite => write!(f, "a control rect isn't a finite number"),
+            HallWayError::TooManySteps(steps) => {
+                write!(f, "the stairs need {} steps but at most {} fit", steps, MAX_STEPS)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HallWayError {}
+
+impl Meshable for HallWay {
+    /// A hallway that can't be meshed is left empty, like a room that can't be.
+    fn mesh(&self) -> Vec<Mesh> {
+        self.try_mesh().unwrap_or_default()
     }
 }
 
@@ -93,6 +171,9 @@ pub struct HallWayTexData {
     pub bottom: MeshTex,
     pub left: MeshTex,
     pub right: MeshTex,
+    /// Steps instead of a ramp for the floor of this segment, `bottom` textures the treads.
+    #[serde(default)]
+    pub stairs: Option<Stairs>,
 }
 
 impl HallWayTexData {
@@ -102,6 +183,35 @@ impl HallWayTexData {
             bottom: mesh_tex.clone(),
             left: mesh_tex.clone(),
             right: mesh_tex,
+            stairs: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stairs {
+    pub steps: StepSize,
+    pub riser: MeshTex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum StepSize {
+    /// This many steps whatever the difference in height is.
+    Count(u16),
+    /// As few steps as it takes with none of them taller than this.
+    Height(f32),
+}
+
+impl Stairs {
+    /// How many risers it takes to climb `rise`.
+    pub fn step_count(&self, rise: f32) -> usize {
+        if rise.abs() < 1e-4 {
+            return 0;
+        }
+        match self.steps {
+            StepSize::Count(count) => count.max(1) as usize,
+            StepSize::Height(height) if height > 0. => (rise.abs() / height).ceil() as usize,
+            StepSize::Height(_) => 1,
         }
     }
 }
@@ -111,6 +221,216 @@ pub struct DoorLocation {
     pub room_index: Option<RoomId>,
     pub door_id: Option<DoorId>,
     pub enabled: bool,
+    /// Index of a junction in the level and of one of its openings, used instead of the room
+    /// and door when set.
+    #[serde(default)]
+    pub junction: Option<(usize, usize)>,
+}
+
+impl DoorLocation {
+    /// Keeps `junction` on the same junction once the one at `removed` is gone from the
+    /// level's list, a location snapped to the removed junction is let go.
+    pub fn junction_removed(&mut self, removed: usize) {
+        match self.junction {
+            Some((junction, _)) if junction == removed => self.let_go(),
+            Some((junction, opening)) if junction > removed => {
+                self.junction = Some((junction - 1, opening))
+            }
+            _ => {}
+        }
+    }
+
+    /// Like [`DoorLocation::junction_removed`] for the opening at `removed` of `junction`.
+    pub fn opening_removed(&mut self, junction: usize, removed: usize) {
+        match self.junction {
+            Some((snapped, opening)) if snapped == junction && opening == removed => self.let_go(),
+            Some((snapped, opening)) if snapped == junction && opening > removed => {
+                self.junction = Some((snapped, opening - 1))
+            }
+            _ => {}
+        }
+    }
+
+    /// Stops following anything, the hallway stays where it last was.
+    fn let_go(&mut self) {
+        self.junction = None;
+        self.enabled = false;
+    }
+
+    fn control_rect(
+        &self,
+        rooms: &HashMap<RoomId, Room>,
+        junctions: &[Junction],
+        away_from: bool,
+    ) -> Option<ControlRect> {
+        if !self.enabled {
+            return None;
+        }
+        if let Some((junction, opening)) = self.junction {
+            return junctions.get(junction)?.get_control_rect(opening, away_from);
+        }
+        rooms
+            .get(&self.room_index?)?
+            .get_control_rect(&self.door_id?, away_from)
+    }
+}
+
+/// A hub for hallways to meet at, like a T-junction or a crossroads. It's a floor and roof
+/// spanning its openings with walls between them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Junction {
+    pub position: Vector3<f32>,
+    pub rotation: Deg<f32>,
+    pub height: f32,
+    pub openings: Vec<JunctionOpening>,
+    pub floor_texture: MeshTex,
+    pub roof_texture: MeshTex,
+    pub wall_texture: MeshTex,
+}
+
+/// Where a hallway can attach to a junction, `distance` out from its center at `angle`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JunctionOpening {
+    pub angle: Deg<f32>,
+    pub distance: f32,
+    pub width: f32,
+}
+
+impl Junction {
+    /// A crossroads with four openings `width` wide.
+    pub fn new(position: Vector3<f32>, width: f32, height: f32, texture: MeshTex) -> Self {
+        Self {
+            position,
+            rotation: Deg(0.),
+            height,
+            openings: (0..4)
+                .map(|i| JunctionOpening {
+                    angle: Deg(90. * i as f32),
+                    distance: width / 2.,
+                    width,
+                })
+                .collect_vec(),
+            floor_texture: texture.clone(),
+            roof_texture: texture.clone(),
+            wall_texture: texture,
+        }
+    }
+
+    /// Outward direction of `opening` on the ground.
+    fn direction(&self, opening: &JunctionOpening) -> Vector2<f32> {
+        Basis2::from_angle(opening.angle + self.rotation).rotate_vector(Vector2::unit_x())
+    }
+
+    /// Opening edges going counter clockwise around the junction, each opening's right end
+    /// then its left end when looking out of it.
+    fn outline(&self) -> Vec<Vector2<f32>> {
+        self.openings
+            .iter()
+            .sorted_by(|a, b| {
+                let angle = |opening: &JunctionOpening| opening.angle.normalize().0;
+                angle(a).total_cmp(&angle(b))
+            })
+            .flat_map(|opening| {
+                let direction = self.direction(opening);
+                let center = self.position.xz() + direction * opening.distance;
+                let left = Vector2::new(-direction.y, direction.x) * (opening.width / 2.);
+                [center - left, center + left]
+            })
+            .collect_vec()
+    }
+
+    /// Where a hallway leaving through `opening` starts, or ends if it's `away_from` it.
+    pub fn get_control_rect(&self, opening: usize, away_from: bool) -> Option<ControlRect> {
+        let opening = self.openings.get(opening)?;
+        let direction = self.direction(opening);
+        let center = self.position.xz() + direction * opening.distance;
+        //the rect's width runs across the opening
+        let mut rotation = Deg(90.) - Deg::from(Rad(direction.y.atan2(direction.x)));
+        if !away_from {
+            rotation += Deg(180.);
+        }
+        Some(ControlRect {
+            position: Vector3::new(center.x, self.position.y, center.y),
+            rotation,
+            size: Vector2::new(opening.width, self.height),
+        })
+    }
+}
+
+impl Meshable for Junction {
+    /// Needs at least two openings to have a floor.
+    fn mesh(&self) -> Vec<Mesh> {
+        if self.openings.len() < 2 {
+            return vec![];
+        }
+        let outline = self.outline();
+        let mut meshs = vec![];
+        let indices = earcutr::earcut(
+            &outline.iter().flat_map(|point| [point.x, point.y]).collect_vec(),
+            &[],
+            2,
+        )
+        .unwrap_or_default();
+        for (y, texture, facing) in [
+            (self.position.y, &self.floor_texture, 1.),
+            (self.position.y + self.height, &self.roof_texture, -1.),
+        ] {
+            let tex_coords = texture
+                .get_tex_coords(&outline.iter().map(|point| (point.x, point.y)).collect_vec());
+            let vertices = outline
+                .iter()
+                .zip(tex_coords)
+                .map(|(point, tex_coords)| MeshVertex {
+                    position: [point.x, y, point.y],
+                    tex_coords,
+                    ..Default::default()
+                })
+                .collect_vec();
+            let mut indices = indices.iter().map(|index| *index as u32).collect_vec();
+            if let Some(triangle) = indices.get(0..3) {
+                let [a, b, c] = [0, 1, 2].map(|i| Vector3::from(vertices[triangle[i] as usize].position));
+                if (b - a).cross(c - a).y * facing < 0. {
+                    indices.reverse();
+                }
+            }
+            meshs.push(Mesh {
+                textrure: texture.id.id.clone(),
+                vertices,
+                indices,
+            });
+        }
+        //walls fill the gaps from the left end of each opening to the right end of the next
+        let mut walls = Mesh {
+            textrure: self.wall_texture.id.id.clone(),
+            vertices: vec![],
+            indices: vec![],
+        };
+        outline
+            .iter()
+            .skip(1)
+            .chain(outline.first())
+            .tuples()
+            .for_each(|(start, end)| {
+                let width = start.distance(*end);
+                if width < 1e-4 {
+                    return;
+                }
+                let (bottom, top) = (self.position.y, self.position.y + self.height);
+                let points = [(start, bottom), (end, bottom), (end, top), (start, top)]
+                    .map(|(point, y)| Vector3::new(point.x, y, point.y));
+                let center = (start + end) / 2.;
+                let inwards = self.position.xz() - center;
+                walls.combine(quad(
+                    points,
+                    [(0., bottom), (width, bottom), (width, top), (0., top)],
+                    Vector3::new(inwards.x, 0., inwards.y),
+                    &self.wall_texture,
+                ));
+            });
+        meshs.push(walls);
+        meshs.iter_mut().for_each(Mesh::calculate_normals);
+        meshs
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,9 +448,88 @@ impl ControlRect {
             size,
         }
     }
-    pub fn gen_mesh(&self, other: &Self, tex: &HallWayTexData) -> Vec<Mesh> {
+    /// Control rects along a curve from `self` to `other`, including both of them. The
+    /// curve leaves each rect at a right angle and the rects along it stay square to it.
+    pub fn curve_to(&self, other: &Self, subdivisions: u16) -> Vec<Self> {
+        let (start, end) = (self.position.xz(), other.position.xz());
+        let chord = end - start;
+        let length = chord.magnitude();
+        if length < 1e-4 {
+            return vec![self.clone(), other.clone()];
+        }
+        //a rect faces along its normal, pointed the way the hallway is going
+        let tangent = |rect: &Self| {
+            let side = rect.side();
+            let forward = Vector2::new(-side.y, side.x);
+            forward * forward.dot(chord).signum() * length
+        };
+        let (start_tangent, end_tangent) = (tangent(self), tangent(other));
+        let mut rects = vec![self.clone()];
+        rects.extend((1..subdivisions).map(|i| {
+            let t = i as f32 / subdivisions as f32;
+            let (t2, t3) = (t * t, t * t * t);
+            let point = start * (2. * t3 - 3. * t2 + 1.)
+                + start_tangent * (t3 - 2. * t2 + t)
+                + end * (-2. * t3 + 3. * t2)
+                + end_tangent * (t3 - t2);
+            let direction = start * (6. * t2 - 6. * t)
+                + start_tangent * (3. * t2 - 4. * t + 1.)
+                + end * (-6. * t2 + 6. * t)
+                + end_tangent * (3. * t2 - 2. * t);
+            let mut side = Vector2::new(direction.y, -direction.x).normalize();
+            //keep the left and right walls on the same sides as the ends
+            if side.dot(self.side().lerp(other.side(), t)) < 0. {
+                side = -side;
+            }
+            let y = self.position.y + (other.position.y - self.position.y) * t;
+            Self {
+                position: Vector3::new(point.x, y, point.y),
+                rotation: Rad(f32::atan2(-side.y, side.x)).into(),
+                size: self.size.lerp(other.size, t),
+            }
+        }));
+        rects.push(other.clone());
+        rects
+    }
+
+    /// Unit vector along the rect's width, on the ground.
+    fn side(&self) -> Vector2<f32> {
+        Basis2::from_angle(-self.rotation).rotate_vector(Vector2::unit_x())
+    }
+
+    /// Length along the ground of the hallway's left wall from `self` to `other`.
+    fn left_distance(&self, other: &Self) -> f32 {
+        (self.position.xz() + self.side() * (self.size.x / 2.))
+            .distance(other.position.xz() + other.side() * (other.size.x / 2.))
+    }
+
+    /// Length along the ground of the hallway's right wall from `self` to `other`.
+    fn right_distance(&self, other: &Self) -> f32 {
+        (self.position.xz() - self.side() * (self.size.x / 2.))
+            .distance(other.position.xz() - other.side() * (other.size.x / 2.))
+    }
+
+    pub fn gen_mesh(&self, other: &Self, tex: &HallWayTexData) -> Result<Vec<Mesh>, HallWayError> {
+        self.gen_mesh_along(other, tex, (0., 0.))
+    }
+
+    fn is_finite(&self) -> bool {
+        self.position.is_finite() && self.rotation.0.is_finite() && self.size.is_finite()
+    }
+
+    /// Like [`ControlRect::gen_mesh`] with the left and right wall textures starting `along`
+    /// the walls instead of at 0.
+    pub fn gen_mesh_along(
+        &self,
+        other: &Self,
+        tex: &HallWayTexData,
+        along: (f32, f32),
+    ) -> Result<Vec<Mesh>, HallWayError> {
+        if !self.is_finite() || !other.is_finite() {
+            return Err(HallWayError::NotFinite);
+        }
         let mut meshs = vec![];
-        let floor_points = vec![
+        let floor_points = [
             {
                 let mut p = (Basis2::from_angle(Deg(180.)-self.rotation).rotate_vector(Vector2::unit_x())
                     * (self.size.x / 2.))
@@ -178,11 +577,12 @@ impl ControlRect {
                 .map(|(i, p3)| MeshVertex {
                     position: (*p3).into(),
                     tex_coords: floor_tex_coords[i],
+                    ..Default::default()
                 })
                 .collect_vec(),
             indices: vec![2, 1, 0, 2, 0, 3],
         };
-        let roof_points = vec![
+        let roof_points = [
             {
                 let mut p = (Basis2::from_angle(Deg(180.)-self.rotation).rotate_vector(Vector2::unit_x())
                     * (self.size.x / 2.))
@@ -230,6 +630,7 @@ impl ControlRect {
                 .map(|(i, p3)| MeshVertex {
                     position: (*p3).into(),
                     tex_coords: roof_tex_coords[i],
+                    ..Default::default()
                 })
                 .collect_vec(),
             indices: vec![0, 1, 2, 3, 0, 2],
@@ -242,13 +643,13 @@ impl ControlRect {
                 * (other.size.x / 2.))
                 + other.position.xz(),
         );
-        let left_tex_points = vec![
-            Vector2::new(0., self.position.y),
-            Vector2::new(0., self.position.y + self.size.y),
-            Vector2::new(left_distance, other.position.y + other.size.y),
-            Vector2::new(left_distance, other.position.y),
+        let left_tex_points = [
+            Vector2::new(along.0, self.position.y),
+            Vector2::new(along.0, self.position.y + self.size.y),
+            Vector2::new(along.0 + left_distance, other.position.y + other.size.y),
+            Vector2::new(along.0 + left_distance, other.position.y),
         ];
-        let left_points = vec![
+        let left_points = [
             {
                 let mut p = (Basis2::from_angle(Deg(180.)-self.rotation).rotate_vector(Vector2::unit_x())
                     * (self.size.x / -2.))
@@ -296,6 +697,7 @@ impl ControlRect {
                 .map(|(i, a)| MeshVertex {
                     position: (*a).into(),
                     tex_coords: left_tex_coords[i],
+                    ..Default::default()
                 })
                 .collect_vec(),
             indices: [0, 2, 1, 0, 3, 2].to_vec(),
@@ -308,13 +710,13 @@ impl ControlRect {
                 * (other.size.x / -2.))
                 + other.position.xz(),
         );
-        let right_tex_points = vec![
-            Vector2::new(0., self.position.y),
-            Vector2::new(0., self.position.y + self.size.y),
-            Vector2::new(right_distance, other.position.y + other.size.y),
-            Vector2::new(right_distance, other.position.y),
+        let right_tex_points = [
+            Vector2::new(along.1, self.position.y),
+            Vector2::new(along.1, self.position.y + self.size.y),
+            Vector2::new(along.1 + right_distance, other.position.y + other.size.y),
+            Vector2::new(along.1 + right_distance, other.position.y),
         ];
-        let right_points = vec![
+        let right_points = [
             {
                 let mut p = (Basis2::from_angle(Deg(180.)-self.rotation).rotate_vector(Vector2::unit_x())
                     * (self.size.x / 2.))
@@ -362,14 +764,246 @@ impl ControlRect {
                 .map(|(i, a)| MeshVertex {
                     position: (*a).into(),
                     tex_coords: right_tex_coords[i],
+                    ..Default::default()
                 })
                 .collect_vec(),
             indices: [2, 3, 0, 2, 0, 1].to_vec(),
         };
-        meshs.push(floor_mesh);
-        meshs.push(roof_mesh);
-        meshs.push(left_mesh);
-        meshs.push(right_mesh);
-        meshs
+        match &tex.stairs {
+            Some(stairs) => {
+                meshs.push(roof_mesh);
+                meshs.append(&mut self.gen_stairs(other, tex, stairs, along)?);
+            }
+            None => {
+                meshs.push(floor_mesh);
+                meshs.push(roof_mesh);
+                meshs.push(left_mesh);
+                meshs.push(right_mesh);
+            }
+        }
+        meshs.iter_mut().for_each(Mesh::calculate_normals);
+        Ok(meshs)
+    }
+
+    /// Treads and risers from `self` to `other` with walls that follow them down, in place of
+    /// the ramp and walls of [`ControlRect::gen_mesh_along`]. The first and last treads are
+    /// level with the ends.
+    fn gen_stairs(
+        &self,
+        other: &Self,
+        tex: &HallWayTexData,
+        stairs: &Stairs,
+        along: (f32, f32),
+    ) -> Result<Vec<Mesh>, HallWayError> {
+        let rise = other.position.y - self.position.y;
+        let steps = stairs.step_count(rise);
+        if steps > MAX_STEPS {
+            return Err(HallWayError::TooManySteps(steps));
+        }
+        let run = 1. / (steps + 1) as f32;
+        let tread_height = |step: usize| {
+            self.position.y + if steps == 0 { 0. } else { rise * step as f32 / steps as f32 }
+        };
+        let left = |t: f32| {
+            (self.position.xz() + self.side() * (self.size.x / 2.))
+                .lerp(other.position.xz() + other.side() * (other.size.x / 2.), t)
+        };
+        let right = |t: f32| {
+            (self.position.xz() - self.side() * (self.size.x / 2.))
+                .lerp(other.position.xz() - other.side() * (other.size.x / 2.), t)
+        };
+        let at = |point: Vector2<f32>, y: f32| Vector3::new(point.x, y, point.y);
+        //risers are seen from the low end
+        let forward = other.position.xz() - self.position.xz();
+        let riser_facing = Vector3::new(forward.x, 0., forward.y) * -rise.signum();
+
+        let mut treads = Mesh {
+            textrure: tex.bottom.id.id.clone(),
+            vertices: vec![],
+            indices: vec![],
+        };
+        let mut risers = Mesh {
+            textrure: stairs.riser.id.id.clone(),
+            vertices: vec![],
+            indices: vec![],
+        };
+        (0..=steps).for_each(|step| {
+            let (start, end, y) = (step as f32 * run, (step + 1) as f32 * run, tread_height(step));
+            let points = [
+                at(left(start), y),
+                at(left(end), y),
+                at(right(end), y),
+                at(right(start), y),
+            ];
+            let tex_points = points.map(|point| (point.x, point.z));
+            treads.combine(quad(points, tex_points, Vector3::unit_y(), &tex.bottom));
+            if step > 0 {
+                let below = tread_height(step - 1);
+                let width = left(start).distance(right(start));
+                let points = [
+                    at(left(start), below),
+                    at(right(start), below),
+                    at(right(start), y),
+                    at(left(start), y),
+                ];
+                let tex_points = [(0., below), (width, below), (width, y), (0., y)];
+                risers.combine(quad(points, tex_points, riser_facing, &stairs.riser));
+            }
+        });
+
+        //the walls go down to the treads so there are no gaps under them
+        let side_wall = |edge: &dyn Fn(f32) -> Vector2<f32>,
+                         texture: &MeshTex,
+                         start_along: f32,
+                         facing_left: bool| {
+            let length = edge(0.).distance(edge(1.));
+            let mut outline = (0..=steps)
+                .flat_map(|step| {
+                    let y = tread_height(step);
+                    [(step as f32 * run, y), ((step + 1) as f32 * run, y)]
+                })
+                .collect_vec();
+            outline.push((1., other.position.y + other.size.y));
+            outline.push((0., self.position.y + self.size.y));
+            let flat = outline.iter().flat_map(|(t, y)| [*t, *y]).collect_vec();
+            let mut indices = earcutr::earcut(&flat, &[], 2).unwrap_or_default();
+            indices.chunks_mut(3).for_each(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| Vector2::from(outline[triangle[i]]));
+                //the left wall winds counter clockwise in (t, y) and the right one clockwise
+                if ((b - a).perp_dot(c - a) > 0.) != facing_left {
+                    triangle.swap(1, 2);
+                }
+            });
+            let tex_coords = texture.get_tex_coords(
+                &outline.iter().map(|(t, y)| (start_along + t * length, *y)).collect_vec(),
+            );
+            Mesh {
+                textrure: texture.id.id.clone(),
+                vertices: outline
+                    .iter()
+                    .zip(tex_coords)
+                    .map(|((t, y), tex_coords)| MeshVertex {
+                        position: at(edge(*t), *y).into(),
+                        tex_coords,
+                        ..Default::default()
+                    })
+                    .collect_vec(),
+                indices: indices.into_iter().map(|index| index as u32).collect_vec(),
+            }
+        };
+        let left_wall = side_wall(&left, &tex.left, along.0, true);
+        let right_wall = side_wall(&right, &tex.right, along.1, false);
+        Ok(vec![treads, risers, left_wall, right_wall])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{level::mesh::TileStyle, renderer::texture::TextureData};
+
+    fn texture(id: &str) -> MeshTex {
+        MeshTex::new(
+            TextureData {
+                id: id.into(),
+                ratio: 1.,
+            },
+            TileStyle::tile_scale(1., true),
+        )
+    }
+
+    fn stairs(steps: StepSize) -> Stairs {
+        Stairs {
+            steps,
+            riser: texture("riser"),
+        }
+    }
+
+    #[test]
+    fn step_count_follows_the_step_size() {
+        assert_eq!(stairs(StepSize::Count(5)).step_count(2.), 5);
+        assert_eq!(stairs(StepSize::Count(5)).step_count(-2.), 5);
+        assert_eq!(stairs(StepSize::Count(0)).step_count(2.), 1);
+        assert_eq!(stairs(StepSize::Height(0.3)).step_count(2.), 7);
+        assert_eq!(stairs(StepSize::Height(0.5)).step_count(2.), 4);
+        assert_eq!(stairs(StepSize::Height(0.)).step_count(2.), 1);
+    }
+
+    #[test]
+    fn flat_stairs_have_no_steps() {
+        assert_eq!(stairs(StepSize::Count(5)).step_count(0.), 0);
+        assert_eq!(stairs(StepSize::Height(0.3)).step_count(0.), 0);
+    }
+
+    /// Risers in a hallway from the ground to 2 up along a curve of `subdivisions` pieces.
+    fn curved_risers(steps: StepSize, subdivisions: u16) -> usize {
+        let mut tex = HallWayTexData::all(texture("wall"));
+        tex.stairs = Some(stairs(steps));
+        let mut hallway = HallWay::new(
+            ControlRect::new(Vector3::new(0., 0., 0.), Deg(0.), Vector2::new(1., 3.)),
+            ControlRect::new(Vector3::new(6., 2., 6.), Deg(90.), Vector2::new(1., 3.)),
+            tex,
+        );
+        hallway.path = HallWayPath::Curved { subdivisions };
+        hallway
+            .try_mesh()
+            .unwrap()
+            .iter()
+            .filter(|mesh| &*mesh.textrure == "riser")
+            .map(|mesh| mesh.vertices.len() / 4)
+            .sum()
+    }
+
+    #[test]
+    fn curved_stairs_share_out_the_step_count() {
+        assert_eq!(curved_risers(StepSize::Count(7), 4), 7);
+        assert_eq!(curved_risers(StepSize::Count(3), 8), 3);
+        assert_eq!(curved_risers(StepSize::Height(0.3), 5), 7);
+        assert_eq!(curved_risers(StepSize::Count(7), 1), 7);
+    }
+
+    #[test]
+    fn too_many_steps_is_an_error() {
+        let mut tex = HallWayTexData::all(texture("wall"));
+        tex.stairs = Some(stairs(StepSize::Height(1e-6)));
+        let hallway = HallWay::new(
+            ControlRect::new(Vector3::new(0., 0., 0.), Deg(0.), Vector2::new(1., 3.)),
+            ControlRect::new(Vector3::new(0., 2., 6.), Deg(0.), Vector2::new(1., 3.)),
+            tex,
+        );
+        assert!(matches!(hallway.try_mesh(), Err(HallWayError::TooManySteps(_))));
+    }
+
+    fn snapped_to(junction: usize, opening: usize) -> DoorLocation {
+        DoorLocation {
+            room_index: None,
+            door_id: None,
+            enabled: true,
+            junction: Some((junction, opening)),
+        }
+    }
+
+    #[test]
+    fn removing_a_junction_keeps_locations_on_the_same_junction() {
+        let [before, removed, after] = [(0, 1), (1, 1), (2, 1)].map(|(junction, opening)| {
+            let mut location = snapped_to(junction, opening);
+            location.junction_removed(1);
+            location
+        });
+        assert_eq!(before.junction, Some((0, 1)));
+        assert_eq!((removed.junction, removed.enabled), (None, false));
+        assert_eq!(after.junction, Some((1, 1)));
+    }
+
+    #[test]
+    fn removing_an_opening_keeps_locations_on_the_same_opening() {
+        let [other, removed, after] = [(0, 2), (1, 1), (1, 2)].map(|(junction, opening)| {
+            let mut location = snapped_to(junction, opening);
+            location.opening_removed(1, 1);
+            location
+        });
+        assert_eq!(other.junction, Some((0, 2)));
+        assert_eq!((removed.junction, removed.enabled), (None, false));
+        assert_eq!(after.junction, Some((1, 1)));
     }
 }
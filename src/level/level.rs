@@ -2,26 +2,178 @@ use crate::{
     camer_control::CameraController,
     renderer::{camera::Camera, texture::TextureData},
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 use cgmath::{Point3, Deg, Vector2};
 use serde::{Deserialize, Serialize};
 
 use super::{
-    hallway::HallWay,
+    hallway::{HallWay, Junction},
     mesh::{Mesh, MeshTex, Meshable},
-    room::{Room, RoomId, Wall},
+    room::{deserialize_unique_ids, Modifier, Room, RoomId, Wall},
+    static_mesh::find_static_mesh,
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct LevelState {
     pub camera_controler: CameraController,
     pub hallways: Vec<HallWay>,
+    #[serde(deserialize_with = "deserialize_unique_ids")]
     pub rooms: HashMap<RoomId,Room>,
+    /// Largest room id handed out so far, see `Room::last_door_id`.
+    #[serde(default)]
+    pub last_room_id: u16,
+    #[serde(default)]
+    pub junctions: Vec<Junction>,
 }
 
 impl LevelState {
     pub fn update(&mut self){
-        self.hallways.iter_mut().for_each(|hallway|{hallway.update_door_location(&self.rooms)});
+        self.hallways.iter_mut().for_each(|hallway|{hallway.update_door_location(&self.rooms, &self.junctions)});
+    }
+    /// Fixes up the hallways snapped to junctions after the junction at `index` was removed
+    /// from `junctions`.
+    pub fn junction_removed(&mut self, index: usize) {
+        self.hallways.iter_mut().for_each(|hallway| {
+            hallway.start_location.junction_removed(index);
+            hallway.end_location.junction_removed(index);
+        });
+    }
+    /// Fixes up the hallways snapped to `junction` after its opening at `index` was removed.
+    pub fn junction_opening_removed(&mut self, junction: usize, index: usize) {
+        self.hallways.iter_mut().for_each(|hallway| {
+            hallway.start_location.opening_removed(junction, index);
+            hallway.end_location.opening_removed(junction, index);
+        });
+    }
+    /// Loads the geometry of every `Modifier::StaticMesh` whose file isn't loaded yet.
+    pub fn load_static_meshes(&mut self, meshes: &[(String, Arc<[u8]>)]) {
+        self.rooms
+            .values_mut()
+            .flat_map(|room| room.moddifiers.iter_mut())
+            .for_each(|modifier| {
+                if let Modifier::StaticMesh { file, data, .. } = modifier {
+                    if data.as_ref().is_none_or(|data| data.file != *file) {
+                        *data = Some(find_static_mesh(meshes, file));
+                    }
+                }
+            });
+    }
+    /// Drops the loaded geometry of the `Modifier::StaticMesh`es using `file`, so the next
+    /// `load_static_meshes` picks up its new contents.
+    pub fn forget_static_mesh(&mut self, file: &str) {
+        self.rooms
+            .values_mut()
+            .flat_map(|room| room.moddifiers.iter_mut())
+            .for_each(|modifier| {
+                if let Modifier::StaticMesh { file: used, data, .. } = modifier {
+                    if used == file {
+                        *data = None;
+                    }
+                }
+            });
+    }
+    /// Every texture reference in the level.
+    pub fn mesh_texs(&self) -> Vec<&MeshTex> {
+        let mut texs = vec![];
+        self.rooms.values().for_each(|room| {
+            texs.extend([&room.floor_texture, &room.roof_texture]);
+            texs.extend(room.walls.iter().map(|wall| &wall.wall_texture));
+            texs.extend(
+                room.floor_holes
+                    .iter()
+                    .chain(room.roof_holes.iter())
+                    .map(|hole| &hole.rim_texture),
+            );
+            room.moddifiers.iter().for_each(|modifier| match modifier {
+                Modifier::Ramp { ramp_texture, wall_texture, bottom_texture, .. } => {
+                    texs.extend([ramp_texture, wall_texture, bottom_texture])
+                }
+                Modifier::Cliff { walls, floor_texture, .. }
+                | Modifier::Pit { walls, floor_texture, .. } => {
+                    texs.extend(walls.iter().map(|wall| &wall.wall_texture));
+                    texs.push(floor_texture);
+                }
+                Modifier::Disc { sides, top_tex, bottom_tex, .. } => {
+                    texs.extend(sides.iter());
+                    texs.extend([top_tex, bottom_tex]);
+                }
+                Modifier::StaticMesh { texture, .. } => texs.push(texture),
+                Modifier::Stairs { tread_texture, riser_texture, side_texture, .. } => {
+                    texs.extend([tread_texture, riser_texture, side_texture])
+                }
+                Modifier::Column { shaft_texture, cap_texture, .. } => {
+                    texs.extend([shaft_texture, cap_texture])
+                }
+            });
+        });
+        self.hallways.iter().for_each(|hallway| {
+            std::iter::once(&hallway.start_texture)
+                .chain(hallway.middle.iter().map(|(_, tex_data)| tex_data))
+                .for_each(|tex_data| {
+                    texs.extend([&tex_data.top, &tex_data.bottom, &tex_data.left, &tex_data.right]);
+                    texs.extend(tex_data.stairs.as_ref().map(|stairs| &stairs.riser));
+                });
+        });
+        self.junctions.iter().for_each(|junction| {
+            texs.extend([&junction.floor_texture, &junction.roof_texture, &junction.wall_texture])
+        });
+        texs
+    }
+    /// Mutable version of [`LevelState::mesh_texs`].
+    pub fn mesh_texs_mut(&mut self) -> Vec<&mut MeshTex> {
+        let mut texs = vec![];
+        self.rooms.values_mut().for_each(|room| {
+            texs.extend([&mut room.floor_texture, &mut room.roof_texture]);
+            texs.extend(room.walls.iter_mut().map(|wall| &mut wall.wall_texture));
+            texs.extend(
+                room.floor_holes
+                    .iter_mut()
+                    .chain(room.roof_holes.iter_mut())
+                    .map(|hole| &mut hole.rim_texture),
+            );
+            room.moddifiers.iter_mut().for_each(|modifier| match modifier {
+                Modifier::Ramp { ramp_texture, wall_texture, bottom_texture, .. } => {
+                    texs.extend([ramp_texture, wall_texture, bottom_texture])
+                }
+                Modifier::Cliff { walls, floor_texture, .. }
+                | Modifier::Pit { walls, floor_texture, .. } => {
+                    texs.extend(walls.iter_mut().map(|wall| &mut wall.wall_texture));
+                    texs.push(floor_texture);
+                }
+                Modifier::Disc { sides, top_tex, bottom_tex, .. } => {
+                    texs.extend(sides.iter_mut());
+                    texs.extend([top_tex, bottom_tex]);
+                }
+                Modifier::StaticMesh { texture, .. } => texs.push(texture),
+                Modifier::Stairs { tread_texture, riser_texture, side_texture, .. } => {
+                    texs.extend([tread_texture, riser_texture, side_texture])
+                }
+                Modifier::Column { shaft_texture, cap_texture, .. } => {
+                    texs.extend([shaft_texture, cap_texture])
+                }
+            });
+        });
+        self.hallways.iter_mut().for_each(|hallway| {
+            std::iter::once(&mut hallway.start_texture)
+                .chain(hallway.middle.iter_mut().map(|(_, tex_data)| tex_data))
+                .for_each(|tex_data| {
+                    texs.extend([
+                        &mut tex_data.top,
+                        &mut tex_data.bottom,
+                        &mut tex_data.left,
+                        &mut tex_data.right,
+                    ]);
+                    texs.extend(tex_data.stairs.as_mut().map(|stairs| &mut stairs.riser));
+                });
+        });
+        self.junctions.iter_mut().for_each(|junction| {
+            texs.extend([
+                &mut junction.floor_texture,
+                &mut junction.roof_texture,
+                &mut junction.wall_texture,
+            ])
+        });
+        texs
     }
     pub fn none() -> Self {
         Self {
@@ -32,6 +184,8 @@ impl LevelState {
             ),
             hallways: vec![],
             rooms: HashMap::new(),
+            last_room_id: 0,
+            junctions: vec![],
         }
     }
     pub fn new(default_tex_id: &TextureData) -> Self {
@@ -51,41 +205,18 @@ impl LevelState {
             ),
         ];
         rooms[0].walls = vec![
-            Wall {
-                local_pos: Vector2::new(-5., 5.),
-                wall_texture: defualt_mesh_tex.clone(),
-            },
-            Wall {
-                local_pos: Vector2::new(-5., -5.),
-                wall_texture: defualt_mesh_tex.clone(),
-            },
-            Wall {
-                local_pos: Vector2::new(-4., -6.),
-                wall_texture: defualt_mesh_tex.clone(),
-            },
-            Wall {
-                local_pos: Vector2::new(4., -6.),
-                wall_texture: defualt_mesh_tex.clone(),
-            },
-            Wall {
-                local_pos: Vector2::new(5., -5.),
-                wall_texture: defualt_mesh_tex.clone(),
-            },
-            Wall {
-                local_pos: Vector2::new(5., 5.),
-                wall_texture: defualt_mesh_tex.clone(),
-            },
-            Wall {
-                local_pos: Vector2::new(4., 6.),
-                wall_texture: defualt_mesh_tex.clone(),
-            },
-            Wall {
-                local_pos: Vector2::new(-4., 6.),
-                wall_texture: defualt_mesh_tex.clone(),
-            },
+            Wall::new(Vector2::new(-5., 5.), defualt_mesh_tex.clone()),
+            Wall::new(Vector2::new(-5., -5.), defualt_mesh_tex.clone()),
+            Wall::new(Vector2::new(-4., -6.), defualt_mesh_tex.clone()),
+            Wall::new(Vector2::new(4., -6.), defualt_mesh_tex.clone()),
+            Wall::new(Vector2::new(5., -5.), defualt_mesh_tex.clone()),
+            Wall::new(Vector2::new(5., 5.), defualt_mesh_tex.clone()),
+            Wall::new(Vector2::new(4., 6.), defualt_mesh_tex.clone()),
+            Wall::new(Vector2::new(-4., 6.), defualt_mesh_tex.clone()),
         ];
         let mut actual_rooms = HashMap::new();
-        actual_rooms.insert(RoomId::new(), rooms.remove(0));
+        let mut last_room_id = 0;
+        actual_rooms.insert(RoomId::next_free(&actual_rooms, &mut last_room_id), rooms.remove(0));
         Self {
             camera_controler: CameraController::new(
                 4.0,
@@ -94,6 +225,8 @@ impl LevelState {
             ),
             hallways: vec![],
             rooms:actual_rooms,
+            last_room_id,
+            junctions: vec![],
         }
     }
 }
@@ -112,6 +245,7 @@ impl Meshable for LevelState {
         });
         meshes.append(&mut rooms);
         meshes.append(&mut hallways);
+        self.junctions.iter().for_each(|junction| meshes.append(&mut junction.mesh()));
         meshes
     }
 }
@@ -2,6 +2,7 @@ use crate::renderer::{
     texture::{TextureData, TextureId},
     vertex::Vertex,
 };
+use cgmath::{InnerSpace, Vector2, Vector3};
 use geo::{coord, BoundingRect, Polygon};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -13,13 +14,18 @@ pub trait Meshable {
 pub struct Mesh {
     pub textrure: TextureId,
     pub vertices: Vec<MeshVertex>,
-    pub indices: Vec<u16>,
+    pub indices: Vec<u32>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MeshVertex {
     pub position: [f32; 3],
     pub tex_coords: [f32; 2],
+    #[serde(default)]
+    pub normal: [f32; 3],
+    /// Points along u of the texture, w is -1 when the texture is mirrored like glTF's.
+    #[serde(default)]
+    pub tangent: [f32; 4],
 }
 
 impl Mesh {
@@ -29,19 +35,83 @@ impl Mesh {
             .map(|mesh_vertex| Vertex {
                 position: mesh_vertex.position,
                 tex_coords: mesh_vertex.tex_coords,
+                normal: mesh_vertex.normal,
+                tangent: mesh_vertex.tangent,
             })
             .collect()
     }
+    /// Sets the normal and tangent of every vertex from the triangles around it, bigger
+    /// triangles count for more. Front faces wind counter clockwise.
+    pub fn calculate_normals(&mut self) {
+        let zero = Vector3::new(0., 0., 0.);
+        let mut sums = vec![(zero, zero, zero); self.vertices.len()];
+        self.indices.chunks_exact(3).for_each(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| &self.vertices[triangle[i] as usize]);
+            let (edge_1, edge_2) = (
+                Vector3::from(b.position) - Vector3::from(a.position),
+                Vector3::from(c.position) - Vector3::from(a.position),
+            );
+            let (uv_1, uv_2) = (
+                Vector2::from(b.tex_coords) - Vector2::from(a.tex_coords),
+                Vector2::from(c.tex_coords) - Vector2::from(a.tex_coords),
+            );
+            let face = edge_1.cross(edge_2);
+            let det = uv_1.perp_dot(uv_2);
+            //triangles with no texture area can't say which way u goes
+            let (u, v) = if det.abs() > f32::EPSILON {
+                (
+                    (edge_1 * uv_2.y - edge_2 * uv_1.y) / det,
+                    (edge_2 * uv_1.x - edge_1 * uv_2.x) / det,
+                )
+            } else {
+                (zero, zero)
+            };
+            triangle.iter().for_each(|index| {
+                let sum = &mut sums[*index as usize];
+                *sum = (sum.0 + face, sum.1 + u, sum.2 + v);
+            });
+        });
+        self.vertices
+            .iter_mut()
+            .zip(sums)
+            .for_each(|(vertex, (face, u, v))| {
+                let normal = if face.magnitude2() > 0. {
+                    face.normalize()
+                } else {
+                    Vector3::unit_y()
+                };
+                let mut tangent = u - normal * normal.dot(u);
+                if tangent.magnitude2() <= f32::EPSILON {
+                    let across = if normal.x.abs() < 0.9 {
+                        Vector3::unit_x()
+                    } else {
+                        Vector3::unit_y()
+                    };
+                    tangent = across - normal * normal.dot(across);
+                }
+                let tangent = tangent.normalize();
+                let handedness = if normal.cross(tangent).dot(v) < 0. {
+                    -1.
+                } else {
+                    1.
+                };
+                vertex.normal = normal.into();
+                vertex.tangent = tangent.extend(handedness).into();
+            });
+    }
+    /// Appends `other` so both can be drawn at once. Panics if it uses a different texture,
+    /// meshes are only combined with ones built from the same texture.
     pub fn combine(&mut self, mut other: Self) {
-        if self.textrure != self.textrure {
-            panic!();
-        }
+        assert_eq!(
+            self.textrure, other.textrure,
+            "only meshes with the same texture can be combined"
+        );
         self.indices.append(
             other
                 .indices
                 .into_iter()
-                .map(|index| index + self.vertices.len() as u16)
-                .collect::<Vec<u16>>()
+                .map(|index| index + self.vertices.len() as u32)
+                .collect::<Vec<u32>>()
                 .as_mut(),
         );
         self.vertices.append(&mut other.vertices);
@@ -66,7 +136,7 @@ impl MeshTex {
         }
     }
     pub fn get_tex_coords(&self, points: &Vec<(f32, f32)>) -> Vec<[f32; 2]> {
-        let bounds = Polygon::new(
+        let Some(bounds) = Polygon::new(
             geo::LineString(
                 points
                     .iter()
@@ -75,8 +145,9 @@ impl MeshTex {
             ),
             vec![],
         )
-        .bounding_rect()
-        .unwrap();
+        .bounding_rect() else {
+            return vec![];
+        };
 
         let helper_closure: Box<dyn Fn(&f32, &f32) -> [f32; 2]> = match self.tile.get_result() {
             TileResultThing::TileSpecific(TileSpecific(x_tiles, y_tiles)) => Box::new(move |x, y| {
@@ -177,5 +248,31 @@ impl TileStyle{
     }
 }
 
-
-
+/// A flat four sided face with its front towards `facing`.
+pub(crate) fn quad(
+    points: [Vector3<f32>; 4],
+    tex_points: [(f32, f32); 4],
+    facing: Vector3<f32>,
+    texture: &MeshTex,
+) -> Mesh {
+    let normal = (points[1] - points[0]).cross(points[2] - points[0]);
+    let indices = if normal.dot(facing) < 0. {
+        vec![0, 2, 1, 0, 3, 2]
+    } else {
+        vec![0, 1, 2, 0, 2, 3]
+    };
+    let tex_coords = texture.get_tex_coords(&tex_points.to_vec());
+    Mesh {
+        textrure: texture.id.id.clone(),
+        vertices: points
+            .into_iter()
+            .zip(tex_coords)
+            .map(|(point, tex_coords)| MeshVertex {
+                position: point.into(),
+                tex_coords,
+                ..Default::default()
+            })
+            .collect_vec(),
+        indices,
+    }
+}
@@ -2,3 +2,5 @@ pub mod hallway;
 pub mod mesh;
 pub mod room;
 pub mod level;
+pub mod static_mesh;
+pub mod validation;
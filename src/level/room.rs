@@ -1,11 +1,11 @@
-use std::{collections::HashMap, f32::consts::PI, marker::PhantomData, ops::Deref, vec};
+use std::{collections::{HashMap, HashSet}, f32::consts::PI, fmt, hash::Hash, marker::PhantomData, ops::Deref, sync::Arc, vec};
 
 use cgmath::{
-    num_traits::Signed, Array, Basis2, InnerSpace, Matrix2, MetricSpace, Deg, Rotation, Rotation2,
-    Vector2, Vector3, VectorSpace,
+    num_traits::Signed, Array, Basis2, ElementWise, InnerSpace, Matrix2, MetricSpace, Deg, Rotation,
+    Rotation2, Vector2, Vector3, VectorSpace,
 };
 use earcutr::{self, earcut};
-use geo::{coord, BooleanOps, CoordsIter, MultiPolygon, Polygon, Rect};
+use geo::{coord, BooleanOps, Contains, MultiPolygon, Polygon, Rect};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use uid::IdU16;
@@ -14,21 +14,22 @@ use crate::ModuloSignedExt;
 
 use super::{
     hallway::ControlRect,
-    mesh::{Mesh, MeshTex, MeshVertex, Meshable},
+    mesh::{quad, Mesh, MeshTex, MeshVertex, Meshable},
+    static_mesh::StaticMeshData,
+    validation::{validate_polygon, PolygonError},
 };
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Copy)]
 pub struct RoomId(pub uid::IdU16<PhantomData<Room>>);
 
 impl RoomId {
-    pub fn new() -> Self {
-        Self(IdU16::<PhantomData<Room>>::new())
-    }
-}
-
-impl Default for RoomId{
-    fn default() -> Self {
-        Self::new()
+    /// Ids are only unique between the rooms in a level, so new ones are picked from the ids already in use
+    /// and `last`, the largest id handed out so far, which is bumped to the new id.
+    pub fn next_free<T>(used: &HashMap<Self, T>, last: &mut u16) -> Self {
+        let id = next_free_id(used.keys().map(|id| id.get()), *last);
+        *last = id;
+        //next_free_id never gives back 0
+        Self(unsafe { IdU16::<PhantomData<Room>>::new_unchecked(id) })
     }
 }
 
@@ -46,10 +47,11 @@ impl<'de> Deserialize<'de> for RoomId {
     where
         D: serde::Deserializer<'de>,
     {
-        Ok(Self(
-            //we get these values from the saved files so they should be safe(hopefully)
-            unsafe { IdU16::<PhantomData<Room>>::new_unchecked(u16::deserialize(deserializer)?) },
-        ))
+        let id = u16::deserialize(deserializer)?;
+        if id == 0 {
+            return Err(serde::de::Error::custom("room ids start at 1"));
+        }
+        Ok(Self(unsafe { IdU16::<PhantomData<Room>>::new_unchecked(id) }))
     }
 }
 
@@ -65,12 +67,25 @@ pub struct Room {
     pub position: Vector3<f32>,
     pub rotation: Deg<f32>,
     pub walls: Vec<Wall>,
+    #[serde(deserialize_with = "deserialize_unique_ids")]
     pub doors: HashMap<DoorId, Door>,
+    /// Largest door id handed out so far, so a deleted door's id isn't given to a new one
+    /// that hallways still pointing at the old door would snap to.
+    #[serde(default)]
+    pub last_door_id: u16,
     pub height: f32,
     pub moddifiers: Vec<Modifier>,
     pub floor_texture: MeshTex,
     pub roof_texture: MeshTex,
     pub name: String,
+    #[serde(default)]
+    pub floor_holes: Vec<Hole>,
+    #[serde(default)]
+    pub roof_holes: Vec<Hole>,
+    /// How thick the walls are, built outwards from the walls' points. 0 keeps them as
+    /// flat planes.
+    #[serde(default)]
+    pub wall_thickness: f32,
 }
 
 impl Room {
@@ -96,36 +111,53 @@ impl Room {
             ],
             moddifiers: vec![],
             doors: HashMap::new(),
+            last_door_id: 0,
             floor_texture: floor_texture.clone(),
             roof_texture: roof_texture.clone(),
+            floor_holes: vec![],
+            roof_holes: vec![],
+            wall_thickness: 0.,
         }
     }
     pub fn new_door(&mut self, door: Door) -> DoorId {
-        let id = DoorId::new();
+        let id = DoorId::next_free(&self.doors, &mut self.last_door_id);
         self.doors.insert(id.clone(), door);
         id
     }
     pub fn get_control_rect(&self, id: &DoorId, away_from: bool) -> Option<ControlRect> {
         let door = self.doors.get(id)?;
+        if self.walls.len() < 2 {
+            return None;
+        }
         let (start, end) = self
             .walls
             .iter()
             .circular_tuple_windows::<(&Wall, &Wall)>()
             .nth((door.wall.modulo(self.walls.len() as isize)) as usize)
             .expect("Wall doesn't exist");
-        let (x, z) =
-            (Matrix2::from_angle(self.rotation) * ({
-                let dist = start.local_pos.distance(end.local_pos);
-                match door.center.1{
-                    HorizontalAlign::Center=>{(start.local_pos+end.local_pos)/2.}
-                    HorizontalAlign::Left => {start.local_pos.lerp(end.local_pos, door.size.x/(2.*dist))},
-                    HorizontalAlign::Right => {start.local_pos.lerp(end.local_pos, 1.-(door.size.x/2.)/(dist))},
-                }
-            })).into();
+        let dist = start.local_pos.distance(end.local_pos);
+        //a wall with no length has nowhere to put the door
+        if dist < 1e-4 {
+            return None;
+        }
+        let along = match door.center.1{
+            HorizontalAlign::Center=>{0.5}
+            HorizontalAlign::Left => {door.size.x/(2.*dist)},
+            HorizontalAlign::Right => {1.-(door.size.x/2.)/(dist)},
+        };
+        //hallways attach to the outside of thick walls
+        let wall_index = door.wall.modulo(self.walls.len() as isize) as usize;
+        let outside = self.outward_normals()[wall_index] * self.wall_thickness(start);
+        let (x, z) = (Matrix2::from_angle(self.rotation)
+            * (start.local_pos.lerp(end.local_pos, along) + outside))
+        .into();
+        let (start_height, end_height) = (self.roof_height(start), self.roof_height(end));
+        let height = start_height + (end_height - start_height) * along;
+        let floor = start.floor_offset + (end.floor_offset - start.floor_offset) * along;
         let y = match door.center.0 {
-            VerticalAlign::Top => self.height-door.size.y,
-            VerticalAlign::Center => (self.height-door.size.y)/2.,
-            VerticalAlign::Bottom => {0.},
+            VerticalAlign::Top => height-door.size.y,
+            VerticalAlign::Center => (floor+height-door.size.y)/2.,
+            VerticalAlign::Bottom => {floor},
         };
 
         let position = Vector3::new(x, y, z) + self.position;
@@ -144,112 +176,476 @@ impl Room {
     }
 }
 
-impl Meshable for Room {
-    fn mesh(&self) -> Vec<super::mesh::Mesh> {
-        let mut meshs: Vec<Mesh> = vec![];
+impl Room {
+    /// Height of the roof above a corner of the room.
+    pub fn roof_height(&self, wall: &Wall) -> f32 {
+        wall.height.unwrap_or(self.height)
+    }
 
-        //floor and roof
-        let mut bottom_left = [f32::INFINITY, f32::INFINITY];
-        let mut top_right = [f32::NEG_INFINITY, f32::NEG_INFINITY];
-        let mut points = self
-            .walls
+    /// Thickness of the wall going from `wall` to the next corner.
+    pub fn wall_thickness(&self, wall: &Wall) -> f32 {
+        wall.thickness.unwrap_or(self.wall_thickness).max(0.)
+    }
+
+    /// Whether the walls go clockwise. Walls face to their left so everything built around
+    /// them has to be flipped to keep facing into the room.
+    fn clockwise(&self) -> bool {
+        self.walls
             .iter()
-            .map(|wall| {
-                let point = Into::<[f32; 2]>::into(wall.local_pos);
-                if point[0] < bottom_left[0] {
-                    bottom_left[0] = point[0]
-                }
-                if point[1] < bottom_left[1] {
-                    bottom_left[1] = point[1]
-                }
-                if point[0] > top_right[0] {
-                    top_right[0] = point[0]
-                }
-                if point[1] > top_right[1] {
-                    top_right[1] = point[1]
+            .circular_tuple_windows()
+            .fold(0., |acc, (a, b): (&Wall, &Wall)| acc + a.local_pos.perp_dot(b.local_pos))
+            .is_sign_negative()
+    }
+
+    /// Unit vectors pointing out of the room behind every wall's face, in the room's local
+    /// space, whichever way the walls go around.
+    fn outward_normals(&self) -> Vec<Vector2<f32>> {
+        let side = if self.clockwise() { -1. } else { 1. };
+        self.walls
+            .iter()
+            .circular_tuple_windows()
+            .map(|(a, b)| {
+                let edge = b.local_pos - a.local_pos;
+                //walls with no length don't face anywhere
+                if edge.magnitude2() < 1e-8 {
+                    return Vector2::new(0., 0.);
                 }
+                let dir = edge.normalize();
+                Vector2::new(dir.y, -dir.x) * side
+            })
+            .collect()
+    }
 
-                point.into()
+    /// Corners of the outside of the walls, where the outer faces of neighbouring walls meet.
+    fn outer_corners(&self) -> Vec<Vector2<f32>> {
+        let normals = self.outward_normals();
+        let len = self.walls.len();
+        (0..len)
+            .map(|i| {
+                let prev = (i + len - 1) % len;
+                let corner = self.walls[i].local_pos;
+                let (prev_offset, next_offset) = (
+                    normals[prev] * self.wall_thickness(&self.walls[prev]),
+                    normals[i] * self.wall_thickness(&self.walls[i]),
+                );
+                let prev_dir = (corner - self.walls[prev].local_pos).normalize();
+                let next_dir = (self.walls[(i + 1) % len].local_pos - corner).normalize();
+                let cross = prev_dir.perp_dot(next_dir);
+                if cross.abs() < 1e-4 {
+                    return corner + next_offset;
+                }
+                //where the offset lines of the two walls cross
+                let along = (next_offset - prev_offset).perp_dot(next_dir) / cross;
+                corner + prev_offset + prev_dir * along
             })
-            .collect::<Vec<Vec<f32>>>();
-        points.push(points.first().unwrap().clone());
-        let signed_area = points.windows(2).fold(0.0, |acc, points| {
-            acc + ((points[1][0] - points[0][0]) * (points[1][1] + points[0][1]))
-        });
-        points.pop();
-        if signed_area.is_sign_negative() {
-            points.reverse();
-        }
+            .collect()
+    }
 
-        let mut input_data = vec![points, vec![]];
-        let (e_points, _, dim) = earcutr::flatten(&input_data);
+    /// Heights of the roof above `points`, interpolated between the corners of the room.
+    pub fn roof_heights(&self, points: &[Vector2<f32>]) -> Vec<f32> {
+        if self.walls.iter().all(|wall| wall.height.is_none()) {
+            return vec![self.height; points.len()];
+        }
+        self.interpolate_corners(points, |wall| self.roof_height(wall))
+    }
 
-        points = input_data.remove(0);
+    /// Heights of the floor under `points`, interpolated between the corners of the room.
+    pub fn floor_heights(&self, points: &[Vector2<f32>]) -> Vec<f32> {
+        if self.walls.iter().all(|wall| wall.floor_offset == 0.) {
+            return vec![0.; points.len()];
+        }
+        self.interpolate_corners(points, |wall| wall.floor_offset)
+    }
 
-        let roof_indecies = earcutr::earcut(&e_points, &[], dim)
-            .expect("floor didn't earcut properly :(")
-            .into_iter()
-            .map(|usize| usize as u16)
-            .collect::<Vec<u16>>();
-        let mut floor_indices = roof_indecies.clone();
-        floor_indices.reverse();
-        let points3 = points
+    fn interpolate_corners(
+        &self,
+        points: &[Vector2<f32>],
+        value: impl Fn(&Wall) -> f32,
+    ) -> Vec<f32> {
+        let corners = self
+            .walls
+            .iter()
+            .flat_map(|wall| [wall.local_pos.x, wall.local_pos.y])
+            .collect_vec();
+        let triangles = earcut(&corners, &[], 2).unwrap_or_default();
+        points
             .iter()
-            .map(|point2| {
-                let mut posititon = (Matrix2::from_angle(self.rotation)
-                    * Vector2 {
-                        x: point2[0],
-                        y: point2[1],
+            .map(|point| {
+                triangles
+                    .chunks(3)
+                    .find_map(|triangle| {
+                        let [a, b, c] = [0, 1, 2].map(|i| self.walls[triangle[i]].local_pos);
+                        let area = (b - a).perp_dot(c - a);
+                        if area == 0. {
+                            return None;
+                        }
+                        let weight_b = (*point - a).perp_dot(c - a) / area;
+                        let weight_c = (b - a).perp_dot(*point - a) / area;
+                        let weight_a = 1. - weight_b - weight_c;
+                        [weight_a, weight_b, weight_c]
+                            .iter()
+                            .all(|weight| *weight >= -1e-4)
+                            .then(|| {
+                                [weight_a, weight_b, weight_c]
+                                    .iter()
+                                    .zip(triangle)
+                                    .map(|(weight, i)| weight * value(&self.walls[*i]))
+                                    .sum()
+                            })
+                    })
+                    //outside of the room so use the closest corner
+                    .unwrap_or_else(|| {
+                        self.walls
+                            .iter()
+                            .min_by(|a, b| {
+                                a.local_pos
+                                    .distance2(*point)
+                                    .total_cmp(&b.local_pos.distance2(*point))
+                            })
+                            .map_or(0., &value)
                     })
-                .extend(0.0);
-                posititon.swap_elements(1, 2);
-                posititon += self.position;
-                posititon
             })
+            .collect()
+    }
+
+    /// Triangulates the outline of the room with `holes` cut out of it, on the ground or
+    /// following the heights of the roof.
+    fn mesh_surface(
+        &self,
+        holes: &[Vec<Vector2<f32>>],
+        roof: bool,
+        texture: &MeshTex,
+    ) -> Result<Mesh, RoomError> {
+        let mut points = self
+            .walls
+            .iter()
+            .map(|wall| vec![wall.local_pos.x, wall.local_pos.y])
+            .collect::<Vec<Vec<f32>>>();
+        if points.is_empty() {
+            return Err(RoomError::Walls(PolygonError::TooFewPoints(0)));
+        }
+        if !self.clockwise() {
+            points.reverse();
+        }
+        let mut input_data = vec![points];
+        input_data.extend(
+            holes
+                .iter()
+                .filter(|hole| hole.len() >= 3)
+                .map(|hole| hole.iter().map(|point| vec![point.x, point.y]).collect_vec()),
+        );
+        let (e_points, e_holes, dim) = earcutr::flatten(&input_data);
+        let mut indices = earcutr::earcut(&e_points, &e_holes, dim)
+            .map_err(|_| RoomError::Walls(PolygonError::Triangulation))?
+            .into_iter()
+            .map(|usize| usize as u32)
+            .collect::<Vec<u32>>();
+        if !roof {
+            indices.reverse();
+        }
+        let points2 = e_points
+            .iter()
+            .tuples()
+            .map(|(x, y)| Vector2::new(*x, *y))
+            .collect_vec();
+        let heights = if roof {
+            self.roof_heights(&points2)
+        } else {
+            self.floor_heights(&points2)
+        };
+        let points3 = points2
+            .into_iter()
+            .zip(heights)
+            .map(|(point, height)| self.to_world(point, height))
             .collect_vec();
-        let roof_tex_coords = self.roof_texture.get_tex_coords(
+        let tex_coords = texture.get_tex_coords(
             &points3
                 .iter()
                 .map(|point| Into::<(f32, f32)>::into(point.xz()))
                 .collect_vec(),
         );
-        let roof_mesh_vertices: Vec<MeshVertex> = points3
-        .iter()
-        .enumerate()
-        .fold(vec![], |mut acc, (i, point)| {
-            acc.push(MeshVertex {
-                position: Into::<[f32; 3]>::into(*point + Vector3::new(0., self.height, 0.)),
-                tex_coords: roof_tex_coords[i],
-            });
-            acc
-        });
-        let floor_tex_coords = self.floor_texture.get_tex_coords(
-            &points3
-            .iter()
-            .map(|point| Into::<(f32, f32)>::into(point.xz()))
-            .collect_vec(),
-        );
-        let floor_mesh_vertices = points3
+        Ok(Mesh {
+            textrure: texture.id.id.clone(),
+            vertices: points3
+                .into_iter()
+                .zip(tex_coords)
+                .map(|(point, tex_coords)| MeshVertex {
+                    position: point.into(),
+                    tex_coords,
+                    ..Default::default()
+                })
+                .collect_vec(),
+            indices,
+        })
+    }
+
+    /// Wall strips around the edge of `hole` from `bottom` to `top` above `heights`, which
+    /// line up with the hole's points. They face into the hole.
+    fn mesh_hole_rim(&self, hole: &Hole, mut heights: Vec<f32>, bottom: f32, top: f32) -> Mesh {
+        let mut points = hole.points.clone();
+        let area = points
             .iter()
-            .enumerate()
-            .fold(vec![], |mut acc, (i, point)| {
-                acc.push(MeshVertex {
-                    position: Into::<[f32; 3]>::into(*point),
-                    tex_coords: floor_tex_coords[i],
+            .circular_tuple_windows()
+            .fold(0., |acc, (a, b)| acc + a.x * b.y - b.x * a.y);
+        //counter clockwise puts the inside of the hole on the left of every edge
+        if area < 0. {
+            points.reverse();
+            heights.reverse();
+        }
+        let mut mesh = Mesh {
+            textrure: hole.rim_texture.id.id.clone(),
+            vertices: vec![],
+            indices: vec![],
+        };
+        if points.len() < 3 || top <= bottom {
+            return mesh;
+        }
+        let mut along = 0.;
+        let corners = points.iter().zip(heights);
+        corners.circular_tuple_windows().for_each(|((a, a_height), (b, b_height))| {
+            let length = a.distance(*b);
+            let tex_coords = hole.rim_texture.get_tex_coords(&vec![
+                (along, bottom),
+                (along + length, bottom),
+                (along + length, top),
+                (along, top),
+            ]);
+            let start = mesh.vertices.len() as u32;
+            [
+                (a, a_height + bottom),
+                (b, b_height + bottom),
+                (b, b_height + top),
+                (a, a_height + top),
+            ]
+            .into_iter()
+                .zip(tex_coords)
+                .for_each(|((point, y), tex_coords)| {
+                    mesh.vertices.push(MeshVertex {
+                        position: self.to_world(*point, y).into(),
+                        tex_coords,
+                        ..Default::default()
+                    })
                 });
-                acc
-            });
-        let floor = Mesh {
-            textrure: self.floor_texture.id.id.clone(),
-            vertices: floor_mesh_vertices,
-            indices: floor_indices,
+            mesh.indices.extend([0, 1, 2, 0, 2, 3].map(|i| start + i));
+            along += length;
+        });
+        mesh
+    }
+
+    /// Triangulates the face of a wall with `openings` cut out of it. `outline` is in the
+    /// wall's space, x along `dir` from `origin` and y up. The face points to the left of `dir`
+    /// unless it's `flipped`, there's no face when the openings cover all of it.
+    fn mesh_wall_face(
+        &self,
+        outline: Polygon<f32>,
+        openings: &MultiPolygon<f32>,
+        origin: Vector2<f32>,
+        dir: Vector2<f32>,
+        texture: &MeshTex,
+        flipped: bool,
+    ) -> Result<Option<Mesh>, earcutr::Error> {
+        //cutting the openings out rather than passing them to earcut as holes keeps
+        //doors that touch the edge of the wall working
+        let pieces = MultiPolygon::new(vec![outline]).difference(openings);
+        //doors can take up the whole face, leaving nothing of it to mesh
+        if pieces.0.is_empty() {
+            return Ok(None);
+        }
+        let mut e_points: Vec<f32> = vec![];
+        let mut indices = vec![];
+        for piece in pieces.iter() {
+            let mut rings = vec![piece.exterior()];
+            rings.extend(piece.interiors());
+            let rings = rings
+                .into_iter()
+                .map(|ring| {
+                    //geo repeats the first point at the end of a ring
+                    ring.coords()
+                        .dropping_back(1)
+                        .map(|coord| vec![coord.x, coord.y])
+                        .collect_vec()
+                })
+                .collect_vec();
+            let (points, holes, dim) = earcutr::flatten(&rings);
+            let start = e_points.len() / dim;
+            indices.extend(
+                earcutr::earcut(points.as_slice(), &holes, dim)?
+                    .into_iter()
+                    .map(|i| (i + start) as u32),
+            );
+            e_points.extend(points);
+        }
+        if flipped {
+            indices.reverse();
+        }
+        let points2 = e_points.into_iter().tuples::<(_, _)>().collect_vec();
+        let tex_coords = texture.get_tex_coords(&points2);
+        Ok(Some(Mesh {
+            textrure: texture.id.id.clone(),
+            vertices: points2
+                .into_iter()
+                .zip(tex_coords)
+                .map(|((x, y), tex_coords)| MeshVertex {
+                    position: self.to_world(origin + dir * x, y).into(),
+                    tex_coords,
+                    ..Default::default()
+                })
+                .collect_vec(),
+            indices,
+        }))
+    }
+
+    /// Faces lining the openings of a thick wall, from its inner face at `origin` to
+    /// `outside` past it. They face into the openings.
+    fn mesh_reveals(
+        &self,
+        outline: &Polygon<f32>,
+        openings: &MultiPolygon<f32>,
+        origin: Vector2<f32>,
+        dir: Vector2<f32>,
+        outside: Vector2<f32>,
+        texture: &MeshTex,
+    ) -> Mesh {
+        let mut mesh = Mesh {
+            textrure: texture.id.id.clone(),
+            vertices: vec![],
+            indices: vec![],
+        };
+        let depth = outside.magnitude();
+        //parts of an opening running along the edge of the wall don't need lining
+        let on_outline = |point: Vector2<f32>| {
+            outline.exterior().lines().any(|line| {
+                let (start, end) = (Vector2::from(line.start.x_y()), Vector2::from(line.end.x_y()));
+                let along = (point - start).dot(end - start) / (end - start).magnitude2();
+                point.distance(start.lerp(end, along.clamp(0., 1.))) < 1e-4
+            })
+        };
+        let cut = openings.intersection(&MultiPolygon::new(vec![outline.clone()]));
+        for opening in cut.iter() {
+            let mut rings = vec![(opening.exterior(), true)];
+            rings.extend(opening.interiors().iter().map(|ring| (ring, false)));
+            for (ring, exterior) in rings {
+                let area = ring.lines().fold(0., |acc, line| acc + line.determinant());
+                //the opening is on the left of counter clockwise outlines and the right of holes
+                let inwards = if (area > 0.) == exterior { 1. } else { -1. };
+                let mut length = 0.;
+                for line in ring.lines() {
+                    let (start, end) =
+                        (Vector2::from(line.start.x_y()), Vector2::from(line.end.x_y()));
+                    let edge = end - start;
+                    let edge_length = edge.magnitude();
+                    if edge_length < 1e-4 || on_outline((start + end) / 2.) {
+                        length += edge_length;
+                        continue;
+                    }
+                    let facing = self.to_world(origin + dir * -edge.y * inwards, edge.x * inwards)
+                        - self.to_world(origin, 0.);
+                    let face = quad(
+                        [
+                            (start, 0.),
+                            (end, 0.),
+                            (end, 1.),
+                            (start, 1.),
+                        ]
+                        .map(|(point, out)| {
+                            self.to_world(origin + dir * point.x + outside * out, point.y)
+                        }),
+                        [
+                            (length, 0.),
+                            (length + edge_length, 0.),
+                            (length + edge_length, depth),
+                            (length, depth),
+                        ],
+                        facing,
+                        texture,
+                    );
+                    mesh.combine(face);
+                    length += edge_length;
+                }
+            }
+        }
+        mesh
+    }
+
+    fn to_world(&self, local_pos: Vector2<f32>, height: f32) -> Vector3<f32> {
+        let mut position = (Matrix2::from_angle(self.rotation) * local_pos).extend(height);
+        position.swap_elements(1, 2);
+        position + self.position
+    }
+
+    /// Checks every outline in the room, the walls, the holes and the modifiers that have
+    /// one, so meshing can't fail on them. Holes and pits also have to be inside the walls.
+    pub fn validate(&self) -> Result<(), RoomError> {
+        let wall_points = self.walls.iter().map(|wall| wall.local_pos).collect_vec();
+        validate_polygon(&wall_points).map_err(RoomError::Walls)?;
+        let polygon = |points: &[Vector2<f32>]| {
+            Polygon::new(points.iter().map(|point| (point.x, point.y)).collect_vec().into(), vec![])
         };
-        let roof = Mesh {
-            textrure: self.roof_texture.id.id.clone(),
-            vertices: roof_mesh_vertices,
-            indices: roof_indecies,
+        let outline = polygon(&wall_points);
+        let cut_out = |points: &[Vector2<f32>]| {
+            validate_polygon(points)?;
+            if !outline.contains(&polygon(points)) {
+                return Err(PolygonError::OutsideRoom);
+            }
+            Ok(())
         };
-        meshs.append(&mut vec![roof, floor]);
+        self.floor_holes.iter().enumerate().try_for_each(|(i, hole)| {
+            cut_out(&hole.points).map_err(|err| RoomError::FloorHole(i, err))
+        })?;
+        self.roof_holes.iter().enumerate().try_for_each(|(i, hole)| {
+            cut_out(&hole.points).map_err(|err| RoomError::RoofHole(i, err))
+        })?;
+        self.moddifiers
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, modifier)| match modifier {
+                Modifier::Cliff { walls, .. } => {
+                    let points = walls.iter().map(|wall| wall.local_pos).collect_vec();
+                    validate_polygon(&points).map_err(|err| RoomError::Modifier(i, err))
+                }
+                Modifier::Pit { walls, .. } => {
+                    let points = walls.iter().map(|wall| wall.local_pos).collect_vec();
+                    cut_out(&points).map_err(|err| RoomError::Modifier(i, err))
+                }
+                Modifier::Disc { sides, .. } if sides.len() < 3 => Err(RoomError::Modifier(
+                    i,
+                    PolygonError::TooFewPoints(sides.len()),
+                )),
+                _ => Ok(()),
+            })?;
+        self.doors.iter().try_for_each(|(id, door)| match &door.shape {
+            DoorShape::Custom(points) => {
+                validate_polygon(points).map_err(|err| RoomError::Door(*id, err))
+            }
+            _ => Ok(()),
+        })
+    }
+
+    /// Like [`Meshable::mesh`] but gives back why the room can't be meshed instead of an
+    /// empty mesh.
+    pub fn try_mesh(&self) -> Result<Vec<Mesh>, RoomError> {
+        self.validate()?;
+        let mut meshs: Vec<Mesh> = vec![];
+
+        //floor and roof
+        //pits are cut out like holes but close themselves off
+        let cut_outs = |holes: &[Hole], roof: bool| {
+            let mut outlines = holes.iter().map(|hole| hole.points.clone()).collect_vec();
+            outlines.extend(self.moddifiers.iter().filter_map(|pit| pit.pit_outline(roof)));
+            outlines
+        };
+        meshs.push(self.mesh_surface(&cut_outs(&self.roof_holes, true), true, &self.roof_texture)?);
+        meshs.push(self.mesh_surface(
+            &cut_outs(&self.floor_holes, false),
+            false,
+            &self.floor_texture,
+        )?);
+        self.floor_holes.iter().for_each(|hole| {
+            meshs.push(self.mesh_hole_rim(hole, self.floor_heights(&hole.points), -hole.depth, 0.));
+        });
+        self.roof_holes.iter().for_each(|hole| {
+            meshs.push(self.mesh_hole_rim(hole, self.roof_heights(&hole.points), 0., hole.depth));
+        });
 
         //walls
         let vec_doors: Vec<Vec<&Door>> = self.doors.values().into_iter().fold(
@@ -262,86 +658,149 @@ impl Meshable for Room {
                 acc
             },
         );
+        let (outward_normals, outer_corners) = (self.outward_normals(), self.outer_corners());
+        let clockwise = self.clockwise();
         self.walls
             .iter()
             .circular_tuple_windows::<(_, _)>()
             .enumerate()
-            .for_each(|(index, (wall_1, wall_2))| {
-                let top_right = [wall_1.local_pos.distance(wall_2.local_pos), self.height];
+            .try_for_each(|(index, (wall_1, wall_2))| {
+                let width = wall_1.local_pos.distance(wall_2.local_pos);
+                let (start_height, end_height) =
+                    (self.roof_height(wall_1), self.roof_height(wall_2));
                 let dir = (wall_2.local_pos - wall_1.local_pos).normalize();
                 let doors = &vec_doors[index];
-                let wall_points = vec![
-                    vec![0., 0.],
-                    vec![top_right[0], 0.],
-                    top_right.to_vec(),
-                    vec![0., top_right[1]],
-                ];
-                let mut holes = doors
-                    .iter()
-                    .fold(MultiPolygon::new(vec![]), |acc, door: &&Door| {
-                        let rect = door.to_rect(top_right[0], top_right[1]);
-                        acc.union(&MultiPolygon::new(vec![Polygon::from(rect)]))
-                    })
-                    .into_iter()
-                    .map(|polygon| {
-                        polygon
-                            .exterior_coords_iter()
-                            .map(|coord| vec![coord.x, coord.y])
-                            .collect_vec()
-                    })
-                    .collect_vec();
-                let mut temp_input = vec![wall_points];
-                temp_input.append(&mut holes);
-                let (e_points, e_holes, dim) = earcutr::flatten(&temp_input);
-                let wall_indecies = earcutr::earcut(e_points.as_slice(), &e_holes, dim)
-                    .expect("wall didn't earcut properly :(");
-
-                let points3 = e_points
-                    .iter()
-                    .tuples::<(_, _)>()
-                    .map(|point2| {
-                        let y = point2.1 + self.position.y;
-                        let (mut x, mut z) = (Matrix2::from_angle(self.rotation)
-                            * (*point2.0 * dir + wall_1.local_pos))
-                            .into();
-                        x += self.position.x;
-                        z += self.position.z;
-                        let position = Vector3::new(x, y, z);
-                        position
-                    })
-                    .collect_vec();
-
-                let wall_tex_coords = wall_1.wall_texture.get_tex_coords(
-                    &e_points
+                let (start_floor, end_floor) = (wall_1.floor_offset, wall_2.floor_offset);
+                let wall_outline = Polygon::new(
+                    geo::LineString::from(vec![
+                        (0., start_floor),
+                        (width, end_floor),
+                        (width, end_height),
+                        (0., start_height),
+                    ]),
+                    vec![],
+                );
+                let openings =
+                    doors
                         .iter()
-                        .tuples::<(_, _)>()
-                        .map(|a| (*a.0, *a.1))
-                        .collect_vec(),
-                ); //points3.iter().map(|point|{Into::<[f32;2]>::into(point.xy())}).collect_vec();
-
-                let wall_mesh = points3.into_iter().enumerate().fold(
-                    Mesh {
-                        textrure: wall_1.wall_texture.id.id.clone(),
-                        vertices: vec![],
-                        indices: wall_indecies
-                            .into_iter()
-                            .map(|usize| usize as u16)
-                            .collect_vec(),
-                    },
-                    |mut acc, (i, point)| {
-                        acc.vertices.push(MeshVertex {
-                            position: point.into(),
-                            tex_coords: wall_tex_coords[i],
+                        .fold(MultiPolygon::new(vec![]), |acc, door: &&Door| {
+                            //doors follow the floor and roof at their middle
+                            let along = door.to_rect(width, 0., 0.).center().x / width;
+                            let opening = door.to_polygon(
+                                width,
+                                start_floor + (end_floor - start_floor) * along,
+                                start_height + (end_height - start_height) * along,
+                            );
+                            acc.union(&MultiPolygon::new(vec![opening]))
                         });
-                        acc
-                    },
+                meshs.extend(
+                    self.mesh_wall_face(
+                        wall_outline.clone(),
+                        &openings,
+                        wall_1.local_pos,
+                        dir,
+                        &wall_1.wall_texture,
+                        clockwise,
+                    )
+                    .map_err(|_| RoomError::Wall(index))?,
                 );
-                meshs.push(wall_mesh);
-            });
-        self.moddifiers.iter().for_each(|modifer| {
-            meshs.append(&mut (modifer.gen_mesh(self.position, self.rotation, self.height)));
-        });
-        meshs
+                let thickness = self.wall_thickness(wall_1);
+                if thickness > 0. {
+                    let outside = outward_normals[index] * thickness;
+                    let along = |point: Vector2<f32>| (point - wall_1.local_pos).dot(dir);
+                    let (outer_start, outer_end) = (
+                        along(outer_corners[index]),
+                        along(outer_corners[(index + 1) % self.walls.len()]),
+                    );
+                    let floor_at = |x: f32| start_floor + (end_floor - start_floor) * x / width;
+                    let roof_at = |x: f32| start_height + (end_height - start_height) * x / width;
+                    let outer_outline = Polygon::new(
+                        geo::LineString::from(vec![
+                            (outer_start, floor_at(outer_start)),
+                            (outer_end, floor_at(outer_end)),
+                            (outer_end, roof_at(outer_end)),
+                            (outer_start, roof_at(outer_start)),
+                        ]),
+                        vec![],
+                    );
+                    meshs.extend(
+                        self.mesh_wall_face(
+                            outer_outline,
+                            &openings,
+                            wall_1.local_pos + outside,
+                            dir,
+                            &wall_1.wall_texture,
+                            !clockwise,
+                        )
+                        .map_err(|_| RoomError::Wall(index))?,
+                    );
+                    //the top of the wall between the two faces
+                    let cap = [
+                        (wall_1.local_pos, start_height),
+                        (wall_2.local_pos, end_height),
+                        (outer_corners[(index + 1) % self.walls.len()], end_height),
+                        (outer_corners[index], start_height),
+                    ];
+                    meshs.push(quad(
+                        cap.map(|(point, height)| self.to_world(point, height)),
+                        cap.map(|(point, _)| {
+                            (along(point), (point - wall_1.local_pos).dot(outside) / thickness)
+                        }),
+                        Vector3::unit_y(),
+                        &wall_1.wall_texture,
+                    ));
+                    meshs.push(self.mesh_reveals(
+                        &wall_outline,
+                        &openings,
+                        wall_1.local_pos,
+                        dir,
+                        outside,
+                        &wall_1.wall_texture,
+                    ));
+                }
+                Ok(())
+            })?;
+        meshs.iter_mut().for_each(Mesh::calculate_normals);
+        self.moddifiers.iter().enumerate().try_for_each(|(i, modifer)| {
+            meshs.append(&mut modifer.gen_mesh(self).map_err(|err| RoomError::Modifier(i, err))?);
+            Ok(())
+        })?;
+        Ok(meshs)
+    }
+}
+
+/// Why a room can't be meshed, holes and modifiers are counted from 0 like in the room's lists.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoomError {
+    Walls(PolygonError),
+    FloorHole(usize, PolygonError),
+    RoofHole(usize, PolygonError),
+    Modifier(usize, PolygonError),
+    Door(DoorId, PolygonError),
+    /// The doors cut the wall starting at this corner into something that can't be meshed.
+    Wall(usize),
+}
+
+impl fmt::Display for RoomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomError::Walls(err) => write!(f, "walls: {}", err),
+            RoomError::FloorHole(i, err) => write!(f, "floor hole {}: {}", i + 1, err),
+            RoomError::RoofHole(i, err) => write!(f, "roof hole {}: {}", i + 1, err),
+            RoomError::Modifier(i, err) => write!(f, "modifier {}: {}", i + 1, err),
+            RoomError::Door(id, err) => write!(f, "door {}: {}", id.get(), err),
+            RoomError::Wall(i) => write!(f, "wall {}: doors don't fit in the wall", i + 1),
+        }
+    }
+}
+
+impl std::error::Error for RoomError {}
+
+impl Meshable for Room {
+    /// A room that can't be meshed is left empty, this runs every frame so the error is
+    /// shown by the editor instead of being logged here.
+    fn mesh(&self) -> Vec<super::mesh::Mesh> {
+        self.try_mesh().unwrap_or_default()
     }
 }
 
@@ -349,6 +808,15 @@ impl Meshable for Room {
 pub struct Wall {
     pub local_pos: Vector2<f32>,
     pub wall_texture: MeshTex,
+    /// Height of the roof at this corner, `None` uses the room's height.
+    #[serde(default)]
+    pub height: Option<f32>,
+    /// How far the floor at this corner is raised above the room's position.
+    #[serde(default)]
+    pub floor_offset: f32,
+    /// Thickness of the wall from this corner to the next, `None` uses the room's thickness.
+    #[serde(default)]
+    pub thickness: Option<f32>,
 }
 
 impl Wall {
@@ -356,6 +824,33 @@ impl Wall {
         Self {
             local_pos: pos,
             wall_texture,
+            height: None,
+            floor_offset: 0.,
+            thickness: None,
+        }
+    }
+}
+
+/// An opening cut out of a room's floor or roof, in the room's local space like its walls.
+/// The rim gets walls `depth` tall going down from the floor or up from the roof.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Hole {
+    pub points: Vec<Vector2<f32>>,
+    pub depth: f32,
+    pub rim_texture: MeshTex,
+}
+
+impl Hole {
+    pub fn new(center: Vector2<f32>, rim_texture: MeshTex) -> Self {
+        Self {
+            points: vec![
+                center + Vector2::new(-0.5, -0.5),
+                center + Vector2::new(0.5, -0.5),
+                center + Vector2::new(0.5, 0.5),
+                center + Vector2::new(-0.5, 0.5),
+            ],
+            depth: 1.,
+            rim_texture,
         }
     }
 }
@@ -376,6 +871,14 @@ pub enum Modifier {
         height: f32,
         floor_texture: MeshTex,
     },
+    /// A recess sunk `depth` into the floor, or up into the roof when `on_roof`, its
+    /// footprint is cut out of the room and closed off at the bottom.
+    Pit {
+        walls: Vec<Wall>,
+        on_roof: bool,
+        depth: f32,
+        floor_texture: MeshTex,
+    },
     Disc {
         pos: Vector3<f32>,
         size: Vector3<f32>,
@@ -383,7 +886,118 @@ pub enum Modifier {
         dir: Deg<f32>,
         top_tex: MeshTex,
         bottom_tex: MeshTex,
+        /// Radii of the top in x and z, the same as the bottom's in `size` when not set.
+        #[serde(default)]
+        top_size: Option<Vector2<f32>>,
+        #[serde(default)]
+        bevel: Option<Bevel>,
     },
+    StaticMesh {
+        file: String,
+        pos: Vector3<f32>,
+        dir: Deg<f32>,
+        scale: Vector3<f32>,
+        texture: MeshTex,
+        #[serde(skip)]
+        data: Option<Arc<StaticMeshData>>,
+    },
+    /// A flight of steps going up along `dir` from `pos`, `run` is the length of the steps
+    /// without any landings.
+    Stairs {
+        pos: Vector3<f32>,
+        dir: Deg<f32>,
+        width: f32,
+        rise: f32,
+        run: f32,
+        steps: u16,
+        /// Thickness of the boards along both sides, without them the stepped sides show.
+        stringers: Option<f32>,
+        landing: Option<Landing>,
+        tread_texture: MeshTex,
+        riser_texture: MeshTex,
+        side_texture: MeshTex,
+    },
+    /// A pillar from the floor to the roof, repeated `count` times `spacing` apart along its
+    /// own x and y.
+    Column {
+        pos: Vector2<f32>,
+        dir: Deg<f32>,
+        radius: f32,
+        shape: ColumnShape,
+        base: Option<ColumnEnd>,
+        capital: Option<ColumnEnd>,
+        count: (u16, u16),
+        spacing: Vector2<f32>,
+        shaft_texture: MeshTex,
+        cap_texture: MeshTex,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ColumnShape {
+    Square,
+    Sided { sides: u16 },
+    Round { segments: u16 },
+}
+
+impl ColumnShape {
+    /// Corners of the cross section counter clockwise, with a radius of 1.
+    pub fn outline(&self) -> Vec<Vector2<f32>> {
+        let sides = match self {
+            ColumnShape::Square => {
+                return vec![
+                    Vector2::new(-1., -1.),
+                    Vector2::new(1., -1.),
+                    Vector2::new(1., 1.),
+                    Vector2::new(-1., 1.),
+                ]
+            }
+            ColumnShape::Sided { sides } => *sides,
+            ColumnShape::Round { segments } => *segments,
+        }
+        .max(3);
+        (0..sides)
+            .map(|i| {
+                let angle = 2. * PI * i as f32 / sides as f32;
+                Vector2::new(angle.cos(), angle.sin())
+            })
+            .collect()
+    }
+}
+
+/// The base or capital of a [`Modifier::Column`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ColumnEnd {
+    pub height: f32,
+    /// Points of the side from the floor or roof at 0 to the shaft at 1 in y, x is the width
+    /// compared to the shaft.
+    pub profile: Vec<Vector2<f32>>,
+}
+
+impl ColumnEnd {
+    pub fn new(height: f32) -> Self {
+        Self {
+            height,
+            profile: vec![Vector2::new(1.5, 0.), Vector2::new(1.5, 0.6), Vector2::new(1., 1.)],
+        }
+    }
+}
+
+/// A chamfer around the top edge of a [`Modifier::Disc`], textured like the side under it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Bevel {
+    /// How far in from the edge the top starts.
+    pub width: f32,
+    /// How far down from the top the sides end.
+    pub height: f32,
+}
+
+/// A flat stretch between the steps of [`Modifier::Stairs`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Landing {
+    /// Steps between landings, there's never one after the last step.
+    pub every: u16,
+    pub depth: f32,
 }
 
 impl PartialEq for Modifier{
@@ -391,19 +1005,30 @@ impl PartialEq for Modifier{
         match (self,other){
             (Modifier::Ramp {..}, Modifier::Ramp {..}) => true,
             (Modifier::Cliff {..}, Modifier::Cliff {..}) => true,
+            (Modifier::Pit {..}, Modifier::Pit {..}) => true,
             (Modifier::Disc {..}, Modifier::Disc {..}) => true,
+            (Modifier::StaticMesh {..}, Modifier::StaticMesh {..}) => true,
+            (Modifier::Stairs {..}, Modifier::Stairs {..}) => true,
+            (Modifier::Column {..}, Modifier::Column {..}) => true,
             _=>false
         }
     }
 }
 
 impl Modifier {
-    pub fn gen_mesh(
-        &self,
-        true_position: Vector3<f32>,
-        true_dir: Deg<f32>,
-        room_height: f32,
-    ) -> Vec<Mesh> {
+    /// The footprint of a pit in the room's floor, or in its roof when `roof`.
+    fn pit_outline(&self, roof: bool) -> Option<Vec<Vector2<f32>>> {
+        match self {
+            Modifier::Pit { walls, on_roof, .. } if *on_roof == roof && walls.len() >= 3 => {
+                Some(walls.iter().map(|wall| wall.local_pos).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Meshes the modifier inside `room`, an outline that can't be triangulated is an error.
+    pub fn gen_mesh(&self, room: &Room) -> Result<Vec<Mesh>, PolygonError> {
+        let (true_position, true_dir) = (room.position, room.rotation);
         let mut meshs = vec![];
         match self {
             Modifier::Ramp {
@@ -452,6 +1077,7 @@ impl Modifier {
                                 Into::<[f32; 3]>::into(position)
                             },
                             tex_coords: ramp_tex_coords[i],
+                            ..Default::default()
                         })
                         .collect_vec(),
                     indices: vec![2, 1, 0, 3, 2, 0],
@@ -485,6 +1111,7 @@ impl Modifier {
                                 Into::<[f32; 3]>::into(position)
                             },
                             tex_coords: front_tex_points[i],
+                            ..Default::default()
                         })
                         .collect_vec(),
                     indices: vec![2, 3, 0, 1, 2, 0],
@@ -512,6 +1139,7 @@ impl Modifier {
                                 Into::<[f32; 3]>::into(position)
                             },
                             tex_coords: left_tex_point[i],
+                            ..Default::default()
                         })
                         .collect_vec(),
                     indices: vec![0, 2, 1],
@@ -537,6 +1165,7 @@ impl Modifier {
                                 Into::<[f32; 3]>::into(position)
                             },
                             tex_coords: left_tex_point[i],
+                            ..Default::default()
                         })
                         .collect_vec(),
                     indices: vec![1, 2, 0],
@@ -568,6 +1197,7 @@ impl Modifier {
                                 Into::<[f32; 3]>::into(position)
                             },
                             tex_coords: bottom_tex_points[i],
+                            ..Default::default()
                         })
                         .collect_vec(),
                     indices: vec![2, 3, 0, 1, 2, 0],
@@ -580,8 +1210,17 @@ impl Modifier {
                 height,
                 floor_texture,
             } => {
-                if walls.len()>0{
+                if !walls.is_empty(){
                     let mut floor_points = walls.iter().map(|wall| wall.local_pos).collect_vec();
+                    //the footprint sits on the room's floor or hangs from its roof
+                    let bases = if *on_roof {
+                        room.roof_heights(&floor_points)
+                            .into_iter()
+                            .map(|roof| roof - *height)
+                            .collect_vec()
+                    } else {
+                        room.floor_heights(&floor_points)
+                    };
                     floor_points.iter_mut().for_each(|point| {
                         *point = Basis2::from_angle(true_dir).rotate_vector(*point);
                     });
@@ -590,7 +1229,7 @@ impl Modifier {
                         .map(|point| vec![point.x, point.y])
                         .collect_vec()]);
                     let mut floor_indices = earcut(e_points.as_slice(), e_holes.as_slice(), dims)
-                        .expect("modifer floor didn't earcut properly :(");
+                        .map_err(|_| PolygonError::Triangulation)?;
                     let floor_tex_points = floor_texture.get_tex_coords(
                         &floor_points
                             .iter()
@@ -619,41 +1258,41 @@ impl Modifier {
                             .map(|(i, (a, b))| MeshVertex {
                                 position: {
                                     let mut position = Vector2::new(a, b).extend(if *on_roof {
-                                        room_height - *height
+                                        bases[i]
                                     } else {
-                                        *height
+                                        bases[i] + *height
                                     });
                                     position.swap_elements(1, 2);
                                     position += true_position;
                                     Into::<[f32; 3]>::into(position)
                                 },
                                 tex_coords: floor_tex_points[i],
+                                ..Default::default()
                             })
                             .collect_vec(),
                         indices: floor_indices
                             .into_iter()
-                            .map(|index| index as u16)
+                            .map(|index| index as u32)
                             .collect_vec(),
                     };
                     meshs.push(floor_mesh);
                     walls
                         .iter()
+                        .zip(bases.iter())
                         .circular_tuple_windows::<(_, _)>()
-                        .for_each(|(wall_1, wall_2)| {
-                            let top_right = [wall_1.local_pos.distance(wall_2.local_pos), *height];
+                        .for_each(|((wall_1, base_1), (wall_2, base_2))| {
+                            let width = wall_1.local_pos.distance(wall_2.local_pos);
                             let dir = (wall_2.local_pos - wall_1.local_pos).normalize();
                             let wall_points = vec![
-                                [0., 0.],
-                                [top_right[0], 0.],
-                                Into::<[f32; 2]>::into(top_right),
-                                [0., top_right[1]],
+                                [0., *base_1],
+                                [width, *base_2],
+                                [width, base_2 + *height],
+                                [0., base_1 + *height],
                             ];
                             let points3 = wall_points
                                 .iter()
                                 .map(|point2| {
-                                    let y = point2[1]
-                                        + true_position.y
-                                        + if *on_roof { room_height - height } else { 0. };
+                                    let y = point2[1] + true_position.y;
                                     let (mut x, mut z) = (Matrix2::from_angle(true_dir)
                                         * (point2[0] * dir + wall_1.local_pos))
                                         .into();
@@ -678,6 +1317,7 @@ impl Modifier {
                                     acc.vertices.push(MeshVertex {
                                         position: point.into(),
                                         tex_coords: wall_tex_coords[i],
+                                        ..Default::default()
                                     });
                                     acc
                                 },
@@ -686,6 +1326,87 @@ impl Modifier {
                         });
                 }
             }
+            Modifier::Pit {
+                walls,
+                on_roof,
+                depth,
+                floor_texture,
+            } => {
+                let points = walls.iter().map(|wall| wall.local_pos).collect_vec();
+                let surface = if *on_roof {
+                    room.roof_heights(&points)
+                } else {
+                    room.floor_heights(&points)
+                };
+                //the bottom of the pit, above the roof for a recess in the ceiling
+                let sunk = if *on_roof { *depth } else { -*depth };
+                let (up, area) = (
+                    if *on_roof { -Vector3::unit_y() } else { Vector3::unit_y() },
+                    points
+                        .iter()
+                        .circular_tuple_windows()
+                        .fold(0., |acc, (a, b)| acc + a.perp_dot(*b)),
+                );
+                let bottom = points
+                    .iter()
+                    .zip(surface.iter())
+                    .map(|(point, height)| room.to_world(*point, height + sunk))
+                    .collect_vec();
+                let mut indices = earcut(
+                    &points.iter().flat_map(|point| [point.x, point.y]).collect_vec(),
+                    &[],
+                    2,
+                )
+                .unwrap_or_default();
+                indices.chunks_mut(3).for_each(|triangle| {
+                    let [a, b, c] = [0, 1, 2].map(|i| bottom[triangle[i]]);
+                    if (b - a).cross(c - a).dot(up) < 0. {
+                        triangle.swap(1, 2);
+                    }
+                });
+                let floor_tex_points = floor_texture.get_tex_coords(
+                    &bottom
+                        .iter()
+                        .map(|point| Into::<(f32, f32)>::into(point.xz()))
+                        .collect_vec(),
+                );
+                meshs.push(Mesh {
+                    textrure: floor_texture.id.id.clone(),
+                    vertices: bottom
+                        .into_iter()
+                        .zip(floor_tex_points)
+                        .map(|(point, tex_coords)| MeshVertex {
+                            position: point.into(),
+                            tex_coords,
+                            ..Default::default()
+                        })
+                        .collect_vec(),
+                    indices: indices.into_iter().map(|index| index as u32).collect_vec(),
+                });
+                walls
+                    .iter()
+                    .zip(surface.iter())
+                    .circular_tuple_windows::<(_, _)>()
+                    .for_each(|((wall_1, height_1), (wall_2, height_2))| {
+                        let width = wall_1.local_pos.distance(wall_2.local_pos);
+                        let dir = wall_2.local_pos - wall_1.local_pos;
+                        //the inside of the pit is on the left when the footprint goes counter
+                        //clockwise
+                        let inwards = Vector2::new(-dir.y, dir.x) * area.signum();
+                        let corners = [
+                            (wall_1.local_pos, height_1 + sunk),
+                            (wall_2.local_pos, height_2 + sunk),
+                            (wall_2.local_pos, *height_2),
+                            (wall_1.local_pos, *height_1),
+                        ];
+                        meshs.push(quad(
+                            corners.map(|(point, height)| room.to_world(point, height)),
+                            [(0., sunk), (width, sunk), (width, 0.), (0., 0.)],
+                            room.to_world(inwards, 0.) - room.to_world(Vector2::new(0., 0.), 0.),
+                            &wall_1.wall_texture,
+                        ));
+                    });
+            }
             Modifier::Disc {
                 pos,
                 size,
@@ -693,106 +1414,146 @@ impl Modifier {
                 dir,
                 top_tex,
                 bottom_tex,
+                top_size,
+                bevel,
             } => {
-                let flat_points = sides
-                    .iter()
-                    .enumerate()
-                    .map(|(side, _)| {
-                        let a = (
-                            ((side as f32 / sides.len() as f32) * 2. * PI + PI / 4.).cos() * size.x,
-                            ((side as f32 / sides.len() as f32) * 2. * PI + PI / 4.).sin() * size.z,
-                        );
-                        Basis2::from_angle(*dir - Deg(PI / 4.))
-                            .rotate_vector(Vector2::new(a.0, a.1))
-                            .into()
-                    })
-                    .collect_vec();
-                let mut flat_indecies = earcut(
-                    flat_points
+                let ring = |radii: Vector2<f32>| {
+                    sides
                         .iter()
-                        .map(|a: &(f32, f32)| vec![a.0, a.1])
-                        .flatten()
+                        .enumerate()
+                        .map(|(side, _)| {
+                            let angle = (side as f32 / sides.len() as f32) * 2. * PI + PI / 4.;
+                            Basis2::from_angle(*dir - Deg(PI / 4.)).rotate_vector(Vector2::new(
+                                angle.cos() * radii.x,
+                                angle.sin() * radii.y,
+                            ))
+                        })
                         .collect_vec()
-                        .as_slice(),
+                };
+                //every ring has the same shape so one triangulation works for the top and bottom
+                let mut flat_indecies = earcut(
+                    &ring(Vector2::new(1., 1.))
+                        .into_iter()
+                        .flat_map(|point| [point.x, point.y])
+                        .collect_vec(),
                     &[],
                     2,
                 )
-                .expect("disk didn't earcut properly :(")
+                .map_err(|_| PolygonError::Triangulation)?
                 .into_iter()
-                .map(|usize| usize as u16)
+                .map(|usize| usize as u32)
                 .collect_vec();
-                let top_tex_points = top_tex.get_tex_coords(&flat_points);
-                let bottom_tex_points = bottom_tex.get_tex_coords(&flat_points);
-                let bottom = Mesh {
-                    textrure: bottom_tex.id.id.clone(),
-                    vertices: flat_points
-                        .iter()
-                        .enumerate()
-                        .map(|(i, point)| MeshVertex {
-                            position: {
-                                let mut position =
-                                    Vector3::new(point.0, size.y / -2., point.1) + *pos;
-                                position = Basis2::from_angle(true_dir)
-                                    .rotate_vector(position.xz())
-                                    .extend(position.y);
-                                position.swap_elements(1, 2);
-                                position += true_position;
-                                position.into()
-                            },
-                            tex_coords: bottom_tex_points[i],
-                        })
-                        .collect_vec(),
-                    indices: flat_indecies.clone(),
+                let (bottom_size, top_size) = (size.xz(), top_size.unwrap_or(size.xz()));
+                let (bottom_y, top_y) = (-size.y / 2., size.y / 2.);
+                //(outline, height) from the bottom to the top
+                let mut layers = vec![(ring(bottom_size), bottom_y)];
+                match bevel {
+                    Some(bevel) => {
+                        let edge_y = top_y - bevel.height.clamp(0., size.y);
+                        let along = if size.y > 0. { (edge_y - bottom_y) / size.y } else { 1. };
+                        layers.push((ring(bottom_size.lerp(top_size, along)), edge_y));
+                        let inset = top_size.map(|radius| (radius - bevel.width).max(0.));
+                        layers.push((ring(inset), top_y));
+                    }
+                    None => layers.push((ring(top_size), top_y)),
+                }
+                let place = |point: Vector2<f32>, y: f32| -> [f32; 3] {
+                    room.to_world(point + pos.xz(), y + pos.y).into()
                 };
-                flat_indecies.reverse();
-                let top = Mesh {
-                    textrure: top_tex.id.id.clone(),
-                    vertices: flat_points
-                        .iter()
-                        .enumerate()
-                        .map(|(i, point)| MeshVertex {
-                            position: {
-                                let mut position =
-                                    Vector3::new(point.0, size.y / 2., point.1) + *pos;
-                                position = Basis2::from_angle(true_dir)
-                                    .rotate_vector(position.xz())
-                                    .extend(position.y);
-                                position.swap_elements(1, 2);
-                                position += true_position;
-                                position.into()
-                            },
-                            tex_coords: top_tex_points[i],
-                        })
-                        .collect_vec(),
-                    indices: flat_indecies.clone(),
+                let flat_mesh = |points: &Vec<Vector2<f32>>, y: f32, texture: &MeshTex, indices| {
+                    let tex_points = texture.get_tex_coords(
+                        &points.iter().map(|point| (point.x, point.y)).collect_vec(),
+                    );
+                    Mesh {
+                        textrure: texture.id.id.clone(),
+                        vertices: points
+                            .iter()
+                            .zip(tex_points)
+                            .map(|(point, tex_coords)| MeshVertex {
+                                position: place(*point, y),
+                                tex_coords,
+                                ..Default::default()
+                            })
+                            .collect_vec(),
+                        indices,
+                    }
                 };
+                let bottom = flat_mesh(&layers[0].0, bottom_y, bottom_tex, flat_indecies.clone());
+                flat_indecies.reverse();
+                let top = flat_mesh(&layers[layers.len() - 1].0, top_y, top_tex, flat_indecies);
                 meshs.append(&mut vec![top, bottom]);
-                flat_points
-                    .iter()
-                    .circular_tuple_windows()
-                    .enumerate()
-                    .for_each(|(i, (point1, point2))| {
-                        let side_distance = Vector2::new(point1.0, point1.1)
-                            .distance(Vector2::new(point2.0, point2.1));
+                (0..sides.len()).for_each(|i| {
+                    let next = (i + 1) % sides.len();
+                    let mut side = Mesh {
+                        textrure: sides[i].id.id.clone(),
+                        vertices: vec![],
+                        indices: vec![],
+                    };
+                    //v follows the slant of each band so sloped sides aren't stretched
+                    let (mut u, mut v) = (0., pos.y - size.y / 2.);
+                    layers.iter().tuple_windows().for_each(|((lower, lower_y), (upper, upper_y))| {
+                        let (lower_width, upper_width) =
+                            (lower[i].distance(lower[next]), upper[i].distance(upper[next]));
+                        let lower_middle = (lower[i] + lower[next]) / 2.;
+                        let upper_middle = (upper[i] + upper[next]) / 2.;
+                        let slant = lower_middle.distance(upper_middle).hypot(upper_y - lower_y);
+                        let shift = u + (lower_width - upper_width) / 2.;
                         let side_tex_points = sides[i].get_tex_coords(&vec![
-                            (0., pos.y - size.y / 2.),
-                            (side_distance, pos.y - size.y / 2.),
-                            (side_distance, pos.y + size.y / 2.),
-                            (0., pos.y + size.y / 2.),
+                            (u, v),
+                            (u + lower_width, v),
+                            (shift + upper_width, v + slant),
+                            (shift, v + slant),
                         ]);
-                        let side = Mesh {
+                        side.combine(Mesh {
                             textrure: sides[i].id.id.clone(),
                             vertices: [
-                                Vector3::new(point1.0, -size.y / 2., point1.1),
-                                Vector3::new(point2.0, -size.y / 2., point2.1),
-                                Vector3::new(point2.0, size.y / 2., point2.1),
-                                Vector3::new(point1.0, size.y / 2., point1.1),
+                                place(lower[i], *lower_y),
+                                place(lower[next], *lower_y),
+                                place(upper[next], *upper_y),
+                                place(upper[i], *upper_y),
                             ]
                             .into_iter()
-                            .enumerate()
-                            .map(|(i, point)| MeshVertex {
+                            .zip(side_tex_points)
+                            .map(|(position, tex_coords)| MeshVertex {
+                                position,
+                                tex_coords,
+                                ..Default::default()
+                            })
+                            .collect_vec(),
+                            indices: vec![2, 1, 0, 3, 2, 0],
+                        });
+                        (u, v) = (shift, v + slant);
+                    });
+                    meshs.push(side);
+                });
+            }
+            Modifier::StaticMesh {
+                pos,
+                dir,
+                scale,
+                texture,
+                data,
+                ..
+            } => {
+                if let Some(data) = data {
+                    let mut indices = data.indices.clone();
+                    if (scale.x * scale.y * scale.z).is_negative() {
+                        indices.reverse();
+                    }
+                    meshs.push(Mesh {
+                        textrure: texture.id.id.clone(),
+                        vertices: data
+                            .positions
+                            .iter()
+                            .zip(data.tex_coords.iter())
+                            .map(|(point, tex_coord)| MeshVertex {
                                 position: {
-                                    let mut position = point + pos;
+                                    let point = Vector3::from(*point).mul_element_wise(*scale);
+                                    let mut position = Basis2::from_angle(*dir)
+                                        .rotate_vector(point.xz())
+                                        .extend(point.y);
+                                    position.swap_elements(1, 2);
+                                    position += *pos;
                                     position = Basis2::from_angle(true_dir)
                                         .rotate_vector(position.xz())
                                         .extend(position.y);
@@ -800,16 +1561,303 @@ impl Modifier {
                                     position += true_position;
                                     position.into()
                                 },
-                                tex_coords: side_tex_points[i],
+                                tex_coords: [
+                                    if texture.fliped[0] { -tex_coord[0] } else { tex_coord[0] }
+                                        + texture.offset[0],
+                                    if texture.fliped[1] { -tex_coord[1] } else { tex_coord[1] }
+                                        + texture.offset[1],
+                                ],
+                                ..Default::default()
                             })
                             .collect_vec(),
-                            indices: vec![2, 1, 0, 3, 2, 0],
+                        indices,
+                    });
+                }
+            }
+            Modifier::Stairs {
+                pos,
+                dir,
+                width,
+                rise,
+                run,
+                steps,
+                stringers,
+                landing,
+                tread_texture,
+                riser_texture,
+                side_texture,
+            } => {
+                let steps = (*steps).max(1) as usize;
+                let (step_height, step_run) = (rise / steps as f32, run / steps as f32);
+                //(start, end, height) of every tread, landings just make a tread longer
+                let mut treads = vec![];
+                let mut length = 0.;
+                for step in 0..steps {
+                    let mut end = length + step_run;
+                    if let Some(Landing { every, depth }) = landing {
+                        if *every > 0 && (step + 1) % *every as usize == 0 && step + 1 < steps {
+                            end += depth.max(0.);
+                        }
+                    }
+                    treads.push((length, end, step_height * (step + 1) as f32));
+                    length = end;
+                }
+                let place = |across: f32, along: f32, y: f32| {
+                    let flat = Basis2::from_angle(*dir).rotate_vector(Vector2::new(across, along));
+                    room.to_world(flat + pos.xz(), y + pos.y)
+                };
+                let origin = place(0., 0., 0.);
+                let (sideways, forward) = (place(1., 0., 0.) - origin, place(0., 1., 0.) - origin);
+
+                let mut tread_mesh = Mesh {
+                    textrure: tread_texture.id.id.clone(),
+                    vertices: vec![],
+                    indices: vec![],
+                };
+                let mut riser_mesh = Mesh {
+                    textrure: riser_texture.id.id.clone(),
+                    vertices: vec![],
+                    indices: vec![],
+                };
+                let mut below = 0.;
+                treads.iter().for_each(|&(start, end, height)| {
+                    tread_mesh.combine(quad(
+                        [
+                            place(0., start, height),
+                            place(*width, start, height),
+                            place(*width, end, height),
+                            place(0., end, height),
+                        ],
+                        [(0., start), (*width, start), (*width, end), (0., end)],
+                        Vector3::unit_y(),
+                        tread_texture,
+                    ));
+                    riser_mesh.combine(quad(
+                        [
+                            place(0., start, below),
+                            place(*width, start, below),
+                            place(*width, start, height),
+                            place(0., start, height),
+                        ],
+                        [(0., below), (*width, below), (*width, height), (0., height)],
+                        -forward,
+                        riser_texture,
+                    ));
+                    below = height;
+                });
+                meshs.push(tread_mesh);
+                meshs.push(riser_mesh);
+
+                //a flat outline in (along, y) standing at `across`
+                let profile = |outline: &[(f32, f32)], across: f32, facing: Vector3<f32>| {
+                    let flat = outline.iter().flat_map(|(along, y)| [*along, *y]).collect_vec();
+                    let points = outline
+                        .iter()
+                        .map(|(along, y)| place(across, *along, *y))
+                        .collect_vec();
+                    let mut indices = earcut(&flat, &[], 2).unwrap_or_default();
+                    indices.chunks_mut(3).for_each(|triangle| {
+                        let [a, b, c] = [0, 1, 2].map(|i| points[triangle[i]]);
+                        if (b - a).cross(c - a).dot(facing) < 0. {
+                            triangle.swap(1, 2);
+                        }
+                    });
+                    Mesh {
+                        textrure: side_texture.id.id.clone(),
+                        vertices: points
+                            .into_iter()
+                            .zip(side_texture.get_tex_coords(&outline.to_vec()))
+                            .map(|(point, tex_coords)| MeshVertex {
+                                position: point.into(),
+                                tex_coords,
+                                ..Default::default()
+                            })
+                            .collect_vec(),
+                        indices: indices.into_iter().map(|index| index as u32).collect_vec(),
+                    }
+                };
+                let mut side_mesh = quad(
+                    [
+                        place(0., length, 0.),
+                        place(*width, length, 0.),
+                        place(*width, length, *rise),
+                        place(0., length, *rise),
+                    ],
+                    [(0., 0.), (*width, 0.), (*width, *rise), (0., *rise)],
+                    forward,
+                    side_texture,
+                );
+                match stringers {
+                    None => {
+                        let mut outline = vec![(0., 0.), (length, 0.)];
+                        outline.extend(
+                            treads
+                                .iter()
+                                .rev()
+                                .flat_map(|&(start, end, height)| [(end, height), (start, height)]),
+                        );
+                        side_mesh.combine(profile(&outline, 0., -sideways));
+                        side_mesh.combine(profile(&outline, *width, sideways));
+                    }
+                    Some(thickness) => {
+                        //their top runs a step above the nosings and stays flat over landings
+                        let mut top = vec![];
+                        treads.iter().for_each(|&(start, end, height)| {
+                            top.push((start, height + step_height));
+                            if end - start > step_run + 1e-4 {
+                                top.push((end - step_run, height + step_height));
+                            }
+                        });
+                        top.push((length, rise + step_height));
+                        let mut outline = vec![(0., 0.), (length, 0.)];
+                        outline.extend(top.iter().rev());
+                        [(-thickness, 0.), (*width, width + thickness)].into_iter().for_each(
+                            |(left, right)| {
+                                side_mesh.combine(profile(&outline, left, -sideways));
+                                side_mesh.combine(profile(&outline, right, sideways));
+                                top.iter().tuple_windows().for_each(|(a, b)| {
+                                    side_mesh.combine(quad(
+                                        [
+                                            place(left, a.0, a.1),
+                                            place(right, a.0, a.1),
+                                            place(right, b.0, b.1),
+                                            place(left, b.0, b.1),
+                                        ],
+                                        [(left, a.0), (right, a.0), (right, b.0), (left, b.0)],
+                                        Vector3::unit_y(),
+                                        side_texture,
+                                    ));
+                                });
+                                [(0., top[0].1, -forward), (length, rise + step_height, forward)]
+                                    .into_iter()
+                                    .for_each(|(along, height, facing)| {
+                                        side_mesh.combine(quad(
+                                            [
+                                                place(left, along, 0.),
+                                                place(right, along, 0.),
+                                                place(right, along, height),
+                                                place(left, along, height),
+                                            ],
+                                            [
+                                                (left, 0.),
+                                                (right, 0.),
+                                                (right, height),
+                                                (left, height),
+                                            ],
+                                            facing,
+                                            side_texture,
+                                        ));
+                                    });
+                            },
+                        );
+                    }
+                }
+                meshs.push(side_mesh);
+            }
+            Modifier::Column {
+                pos,
+                dir,
+                radius,
+                shape,
+                base,
+                capital,
+                count,
+                spacing,
+                shaft_texture,
+                cap_texture,
+            } => {
+                let outline = shape.outline();
+                let rotation = Basis2::from_angle(*dir);
+                let end_height =
+                    |end: &Option<ColumnEnd>| end.as_ref().map_or(0., |end| end.height.max(0.));
+                let mut shaft_mesh = Mesh {
+                    textrure: shaft_texture.id.id.clone(),
+                    vertices: vec![],
+                    indices: vec![],
+                };
+                let mut cap_mesh = Mesh {
+                    textrure: cap_texture.id.id.clone(),
+                    vertices: vec![],
+                    indices: vec![],
+                };
+                (0..count.0.max(1))
+                    .cartesian_product(0..count.1.max(1))
+                    .for_each(|(x, y)| {
+                        let offset = Vector2::new(x as f32 * spacing.x, y as f32 * spacing.y);
+                        let center = pos + rotation.rotate_vector(offset);
+                        let (bottom, top) =
+                            (room.floor_heights(&[center])[0], room.roof_heights(&[center])[0]);
+                        //the ends get squashed when the room is too low for them
+                        let squash = ((top - bottom) / (end_height(base) + end_height(capital)))
+                            .clamp(0., 1.);
+                        //(width, height, is on the shaft) going up the side of the column
+                        let mut silhouette = vec![];
+                        if let Some(end) = base {
+                            silhouette.extend(end.profile.iter().map(|point| {
+                                let rise = point.y.clamp(0., 1.) * end_height(base) * squash;
+                                (point.x, bottom + rise, false)
+                            }));
+                        }
+                        silhouette.push((1., bottom + end_height(base) * squash, false));
+                        silhouette.push((1., top - end_height(capital) * squash, true));
+                        if let Some(end) = capital {
+                            silhouette.extend(end.profile.iter().rev().map(|point| {
+                                let drop = point.y.clamp(0., 1.) * end_height(capital) * squash;
+                                (point.x, top - drop, false)
+                            }));
+                        }
+                        let place = |corner: Vector2<f32>, width: f32, height: f32| {
+                            let offset = rotation.rotate_vector(corner * width * *radius);
+                            room.to_world(center + offset, height)
                         };
-                        meshs.push(side);
+                        let mut up_side = bottom;
+                        silhouette.iter().tuple_windows().for_each(
+                            |(&(width0, y0, _), &(width1, y1, on_shaft))| {
+                                let side_length = (*radius * (width1 - width0)).hypot(y1 - y0);
+                                if side_length < 1e-4 {
+                                    return;
+                                }
+                                let (mesh, texture) = if on_shaft {
+                                    (&mut shaft_mesh, shaft_texture)
+                                } else {
+                                    (&mut cap_mesh, cap_texture)
+                                };
+                                let mut around = 0.;
+                                outline.iter().circular_tuple_windows().for_each(|(a, b)| {
+                                    let outwards = (place((a + b) / 2., 1., 0.)
+                                        - room.to_world(center, 0.))
+                                    .normalize();
+                                    let facing = outwards * (y1 - y0)
+                                        + Vector3::unit_y() * *radius * (width0 - width1);
+                                    let edge_length = a.distance(*b) * *radius;
+                                    mesh.combine(quad(
+                                        [
+                                            place(*a, width0, y0),
+                                            place(*b, width0, y0),
+                                            place(*b, width1, y1),
+                                            place(*a, width1, y1),
+                                        ],
+                                        [
+                                            (around, up_side),
+                                            (around + edge_length, up_side),
+                                            (around + edge_length, up_side + side_length),
+                                            (around, up_side + side_length),
+                                        ],
+                                        facing,
+                                        texture,
+                                    ));
+                                    around += edge_length;
+                                });
+                                up_side += side_length;
+                            },
+                        );
                     });
+                meshs.push(shaft_mesh);
+                meshs.push(cap_mesh);
             }
         };
-        meshs
+        meshs.iter_mut().for_each(Mesh::calculate_normals);
+        Ok(meshs)
     }
 }
 
@@ -819,17 +1867,19 @@ pub struct Door {
     pub offset: Vector2<f32>,
     pub size: Vector2<f32>,
     pub center:(VerticalAlign,HorizontalAlign),
+    #[serde(default)]
+    pub shape: DoorShape,
 }
 
 impl Door {
-    pub fn to_rect(&self, width: f32, height: f32) -> Rect<f32> {
+    pub fn to_rect(&self, width: f32, floor: f32, height: f32) -> Rect<f32> {
         let (top, bottom) = match self.center.0 {
             VerticalAlign::Top => (height + self.offset.y, height + self.offset.y - self.size.y),
             VerticalAlign::Center => (
-                height / 2. + self.offset.y + self.size.y / 2.,
-                height / 2. + self.offset.y - self.size.y / 2.,
+                (floor + height) / 2. + self.offset.y + self.size.y / 2.,
+                (floor + height) / 2. + self.offset.y - self.size.y / 2.,
             ),
-            VerticalAlign::Bottom => (self.offset.y + self.size.y, self.offset.y),
+            VerticalAlign::Bottom => (floor + self.offset.y + self.size.y, floor + self.offset.y),
         };
         let (left, right) = match self.center.1 {
             HorizontalAlign::Left => (self.offset.x, self.offset.x + self.size.x),
@@ -841,6 +1891,63 @@ impl Door {
         };
         Rect::new(coord! {x:right,y:top}, coord! {x:left,y:bottom})
     }
+
+    /// The opening cut into the wall, it always fits inside [`Door::to_rect`].
+    pub fn to_polygon(&self, width: f32, floor: f32, height: f32) -> Polygon<f32> {
+        let rect = self.to_rect(width, floor, height);
+        let (min, max) = (rect.min(), rect.max());
+        let (half_width, center_x) = (rect.width() / 2., rect.center().x);
+        let points = match &self.shape {
+            DoorShape::Rectangle => return Polygon::from(rect),
+            DoorShape::Arch { segments } => {
+                //a door shorter than half its width gets a flattened arch
+                let rise = half_width.min(rect.height());
+                let spring = max.y - rise;
+                let segments = (*segments).max(1);
+                let mut points = vec![coord! {x:min.x,y:min.y}, coord! {x:max.x,y:min.y}];
+                points.extend((0..=segments).map(|i| {
+                    let angle = PI * i as f32 / segments as f32;
+                    coord! {x:center_x + angle.cos() * half_width, y:spring + angle.sin() * rise}
+                }));
+                points
+            }
+            DoorShape::Circle { segments } => {
+                let segments = (*segments).max(3);
+                let (center_y, half_height) = (rect.center().y, rect.height() / 2.);
+                (0..segments)
+                    .map(|i| {
+                        let angle = 2. * PI * i as f32 / segments as f32;
+                        coord! {
+                            x:center_x + angle.cos() * half_width,
+                            y:center_y + angle.sin() * half_height
+                        }
+                    })
+                    .collect_vec()
+            }
+            DoorShape::Custom(points) => points
+                .iter()
+                .map(|point| {
+                    coord! {x:min.x + point.x * rect.width(), y:min.y + point.y * rect.height()}
+                })
+                .collect_vec(),
+        };
+        Polygon::new(geo::LineString(points), vec![])
+    }
+}
+
+/// The outline of a door's opening inside its rectangle. Hallways attached to the door
+/// always use the whole rectangle.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub enum DoorShape {
+    #[default]
+    Rectangle,
+    /// Straight sides with a half circle on top as wide as the door.
+    Arch { segments: u16 },
+    /// A circle, or an ellipse if the door isn't square.
+    Circle { segments: u16 },
+    /// Points where (0, 0) is the bottom left of the door and (1, 1) the top right, for
+    /// pointed arches and anything else.
+    Custom(Vec<Vector2<f32>>),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -860,14 +1967,13 @@ pub enum HorizontalAlign {
 pub struct DoorId(pub uid::IdU16<PhantomData<Door>>);
 
 impl DoorId {
-    pub fn new() -> Self {
-        Self(IdU16::<PhantomData<Door>>::new())
-    }
-}
-
-impl Default for DoorId{
-    fn default() -> Self {
-        Self::new()
+    /// Ids are only unique between the doors in a room, so new ones are picked from the ids already in use
+    /// and `last`, the largest id handed out so far, which is bumped to the new id.
+    pub fn next_free<T>(used: &HashMap<Self, T>, last: &mut u16) -> Self {
+        let id = next_free_id(used.keys().map(|id| id.get()), *last);
+        *last = id;
+        //next_free_id never gives back 0
+        Self(unsafe { IdU16::<PhantomData<Door>>::new_unchecked(id) })
     }
 }
 
@@ -885,10 +1991,11 @@ impl<'de> Deserialize<'de> for DoorId {
     where
         D: serde::Deserializer<'de>,
     {
-        Ok(Self(
-            //we get these values from the saved files so they should be safe(hopefully)
-            unsafe { IdU16::<PhantomData<Door>>::new_unchecked(u16::deserialize(deserializer)?) },
-        ))
+        let id = u16::deserialize(deserializer)?;
+        if id == 0 {
+            return Err(serde::de::Error::custom("door ids start at 1"));
+        }
+        Ok(Self(unsafe { IdU16::<PhantomData<Door>>::new_unchecked(id) }))
     }
 }
 
@@ -899,6 +2006,54 @@ impl Deref for DoorId {
     }
 }
 
+/// One past the largest id in `used` or `last` so ids of deleted items aren't handed out again,
+/// falls back to the smallest unused id once the largest possible one is taken.
+fn next_free_id(used: impl Iterator<Item = u16>, last: u16) -> u16 {
+    let used: HashSet<u16> = used.collect();
+    match used.iter().copied().max().unwrap_or(0).max(last) {
+        u16::MAX => (1..=u16::MAX)
+            .find(|id| !used.contains(id))
+            .expect("every id is already used"),
+        largest => largest + 1,
+    }
+}
+
+/// Deserializes an id keyed map, erroring on repeated ids instead of letting the last one win.
+pub(crate) fn deserialize_unique_ids<'de, D, K, V>(
+    deserializer: D,
+) -> Result<HashMap<K, V>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    K: Deserialize<'de> + Deref<Target = IdU16<PhantomData<V>>> + Hash + Eq,
+    V: Deserialize<'de>,
+{
+    struct UniqueIdVisitor<K, V>(PhantomData<(K, V)>);
+    impl<'de, K, V> serde::de::Visitor<'de> for UniqueIdVisitor<K, V>
+    where
+        K: Deserialize<'de> + Deref<Target = IdU16<PhantomData<V>>> + Hash + Eq,
+        V: Deserialize<'de>,
+    {
+        type Value = HashMap<K, V>;
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a map with unique ids")
+        }
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            let mut values = HashMap::new();
+            while let Some((key, value)) = map.next_entry::<K, V>()? {
+                let id = key.get();
+                if values.insert(key, value).is_some() {
+                    return Err(serde::de::Error::custom(format!("duplicate id {}", id)));
+                }
+            }
+            Ok(values)
+        }
+    }
+    deserializer.deserialize_map(UniqueIdVisitor(PhantomData))
+}
+
 trait Wraper<T>
 where
     Self: Sized,
@@ -924,3 +2079,205 @@ impl<T> Chain for T {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{level::mesh::TileStyle, renderer::texture::TextureData};
+
+    #[test]
+    fn next_free_id_starts_at_one() {
+        assert_eq!(next_free_id([].into_iter(), 0), 1);
+    }
+
+    #[test]
+    fn next_free_id_skips_deleted_ids() {
+        assert_eq!(next_free_id([1, 2].into_iter(), 0), 3);
+        //3 was the largest id before it got deleted
+        assert_eq!(next_free_id([1, 2].into_iter(), 3), 4);
+        assert_eq!(next_free_id([].into_iter(), 3), 4);
+    }
+
+    #[test]
+    fn next_free_id_reuses_gaps_once_the_largest_id_is_taken() {
+        assert_eq!(next_free_id([1, 3, u16::MAX].into_iter(), 0), 2);
+        assert_eq!(next_free_id([1].into_iter(), u16::MAX), 2);
+    }
+
+    #[test]
+    fn next_free_bumps_last() {
+        let mut doors = HashMap::new();
+        let mut last = 0;
+        let id = DoorId::next_free(&doors, &mut last);
+        doors.insert(id, ());
+        doors.remove(&id);
+        assert_ne!(DoorId::next_free(&doors, &mut last), id);
+        assert_eq!(last, 2);
+    }
+
+    #[derive(Deserialize)]
+    struct Doors {
+        #[serde(deserialize_with = "deserialize_unique_ids")]
+        doors: HashMap<DoorId, Door>,
+    }
+
+    fn doors(ids: &[u16]) -> String {
+        let door = ron::to_string(&Door {
+            wall: 0,
+            offset: Vector2::new(0., 0.),
+            size: Vector2::new(1., 3.),
+            center: (VerticalAlign::Bottom, HorizontalAlign::Center),
+            shape: DoorShape::Rectangle,
+        })
+        .unwrap();
+        format!(
+            "(doors: {{{}}})",
+            ids.iter().map(|id| format!("{}: {}", id, door)).join(", ")
+        )
+    }
+
+    #[test]
+    fn deserialize_unique_ids_reads_unique_ids() {
+        let doors: Doors = ron::from_str(&doors(&[1, 2, 5])).unwrap();
+        assert_eq!(
+            doors.doors.keys().map(|id| id.get()).sorted().collect_vec(),
+            vec![1, 2, 5]
+        );
+    }
+
+    #[test]
+    fn deserialize_unique_ids_rejects_duplicates() {
+        let err = ron::from_str::<Doors>(&doors(&[1, 2, 1])).err().unwrap();
+        assert!(err.to_string().contains("duplicate id 1"));
+    }
+
+    #[test]
+    fn deserialize_unique_ids_rejects_zero() {
+        assert!(ron::from_str::<Doors>(&doors(&[0])).is_err());
+    }
+
+    fn texture() -> MeshTex {
+        MeshTex::new(
+            TextureData {
+                id: "default".into(),
+                ratio: 1.,
+            },
+            TileStyle::tile_scale(1., true),
+        )
+    }
+
+    /// A 2 by 2 room around its origin.
+    fn room() -> Room {
+        let origin = Vector3::new(0., 0., 0.);
+        Room::new("room".into(), origin, Deg(0.), 3., texture(), texture(), texture())
+    }
+
+    #[test]
+    fn holes_have_to_be_inside_the_walls() {
+        let mut room = room();
+        room.floor_holes.push(Hole::new(Vector2::new(0., 0.), texture()));
+        assert_eq!(room.validate(), Ok(()));
+        room.roof_holes.push(Hole::new(Vector2::new(1., 0.), texture()));
+        assert_eq!(room.validate(), Err(RoomError::RoofHole(0, PolygonError::OutsideRoom)));
+    }
+
+    #[test]
+    fn pits_have_to_be_inside_the_walls() {
+        let mut room = room();
+        let walls = [(-3., -0.5), (0.5, -0.5), (0.5, 0.5), (-3., 0.5)]
+            .map(|(x, y)| Wall::new(Vector2::new(x, y), texture()))
+            .to_vec();
+        room.moddifiers.push(Modifier::Pit {
+            walls,
+            on_roof: false,
+            depth: 1.,
+            floor_texture: texture(),
+        });
+        assert_eq!(room.validate(), Err(RoomError::Modifier(0, PolygonError::OutsideRoom)));
+        assert!(room.try_mesh().is_err());
+    }
+
+    #[test]
+    fn outward_normals_point_out_whichever_way_the_walls_go() {
+        let mut room = room();
+        let points_out = |room: &Room| {
+            room.walls
+                .iter()
+                .circular_tuple_windows()
+                .zip(room.outward_normals())
+                .all(|((a, b), normal): ((&Wall, &Wall), _)| {
+                    ((a.local_pos + b.local_pos) / 2.).dot(normal) > 0.
+                })
+        };
+        assert!(points_out(&room));
+        room.walls.reverse();
+        assert!(points_out(&room));
+    }
+
+    #[test]
+    fn walls_face_into_the_room_whichever_way_they_go() {
+        let mut room = room();
+        room.walls.reverse();
+        let meshes = room.try_mesh().unwrap();
+        //the roof and floor come first
+        meshes[2..].iter().flat_map(|mesh| &mesh.vertices).for_each(|vertex| {
+            let position = Vector3::from(vertex.position);
+            let to_center = Vector3::new(0., position.y, 0.) - position;
+            assert!(Vector3::from(vertex.normal).dot(to_center) > 0.);
+        });
+    }
+
+    #[test]
+    fn floor_heights_follow_the_corners() {
+        let mut room = room();
+        assert_eq!(room.floor_heights(&[Vector2::new(0.5, 0.5)]), vec![0.]);
+        room.walls[0].floor_offset = 1.;
+        let heights = room.floor_heights(&[
+            Vector2::new(-1., -1.),
+            Vector2::new(0., -1.),
+            Vector2::new(1., 1.),
+            //outside of the room takes the closest corner
+            Vector2::new(-3., -2.),
+        ]);
+        heights
+            .iter()
+            .zip([1., 0.5, 0., 1.])
+            .for_each(|(height, expected)| assert!((height - expected).abs() < 1e-5));
+    }
+
+    #[test]
+    fn doors_can_fill_the_whole_wall() {
+        let mut room = room();
+        room.new_door(Door {
+            wall: 0,
+            offset: Vector2::new(0., 0.),
+            size: Vector2::new(2., 3.),
+            center: (VerticalAlign::Bottom, HorizontalAlign::Center),
+            shape: DoorShape::default(),
+        });
+        let faces = |room: &Room| room.try_mesh().unwrap().len();
+        //the floor, the roof and the three walls left
+        assert_eq!(faces(&room), 5);
+        room.wall_thickness = 0.2;
+        assert!(faces(&room) > 5);
+    }
+
+    #[test]
+    fn repeated_corners_dont_place_doors_at_nan() {
+        let mut room = room();
+        room.walls.insert(1, room.walls[1].clone());
+        let door = |wall| Door {
+            wall,
+            offset: Vector2::new(0., 0.),
+            size: Vector2::new(1., 2.),
+            center: (VerticalAlign::Bottom, HorizontalAlign::Left),
+            shape: DoorShape::default(),
+        };
+        let on_nothing = room.new_door(door(1));
+        let on_wall = room.new_door(door(2));
+        assert!(room.get_control_rect(&on_nothing, true).is_none());
+        let rect = room.get_control_rect(&on_wall, true).unwrap();
+        assert!(rect.position.is_finite());
+        assert!(matches!(room.try_mesh(), Err(RoomError::Walls(PolygonError::DuplicatePoint(..)))));
+    }
+}
@@ -0,0 +1,137 @@
+use std::{io::BufReader, sync::Arc};
+
+use anyhow::anyhow;
+use cgmath::{Matrix4, SquareMatrix, Transform};
+use itertools::Itertools;
+
+use crate::application_state::game_folder_structure::FileExtension;
+
+/// Geometry of a mesh file from the game folder's `meshes/` directory, in the file's own space.
+#[derive(Debug, Clone, Default)]
+pub struct StaticMeshData {
+    pub file: String,
+    pub positions: Vec<[f32; 3]>,
+    pub tex_coords: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+impl StaticMeshData {
+    pub const EXTENSIONS: [&'static str; 3] = ["obj", "gltf", "glb"];
+
+    /// Parses an obj or gltf/glb file. Materials of obj files and external buffers of
+    /// gltf files aren't loaded, so a `.gltf` needs its buffers embedded.
+    pub fn from_bytes(file: &str, data: &[u8]) -> anyhow::Result<Self> {
+        let mut mesh_data = Self {
+            file: file.to_string(),
+            ..Default::default()
+        };
+        if file.has_extension(&["obj"]) {
+            let (models, _) = tobj::load_obj_buf(
+                &mut BufReader::new(data),
+                &tobj::LoadOptions {
+                    single_index: true,
+                    triangulate: true,
+                    ..Default::default()
+                },
+                |_| Err(tobj::LoadError::OpenFileFailed),
+            )?;
+            models.into_iter().try_for_each(|model| {
+                let mesh = model.mesh;
+                let positions = mesh
+                    .positions
+                    .into_iter()
+                    .tuples()
+                    .map(|(x, y, z)| [x, y, z])
+                    .collect_vec();
+                let tex_coords = if mesh.texcoords.is_empty() {
+                    vec![[0., 0.]; positions.len()]
+                } else {
+                    //obj puts the origin of the image at the bottom left
+                    mesh.texcoords
+                        .into_iter()
+                        .tuples()
+                        .map(|(u, v)| [u, 1. - v])
+                        .collect_vec()
+                };
+                mesh_data.append(positions, tex_coords, mesh.indices)
+            })?;
+        } else if file.has_extension(&["gltf", "glb"]) {
+            let (document, buffers, _) = gltf::import_slice(data)?;
+            let scene = document
+                .default_scene()
+                .or_else(|| document.scenes().next())
+                .ok_or(anyhow!("{} has no scenes", file))?;
+            let mut nodes = scene
+                .nodes()
+                .map(|node| (node, Matrix4::identity()))
+                .collect_vec();
+            while let Some((node, parent_transform)) = nodes.pop() {
+                let transform = parent_transform * Matrix4::from(node.transform().matrix());
+                if let Some(mesh) = node.mesh() {
+                    mesh.primitives().try_for_each(|primitive| {
+                        let reader =
+                            primitive.reader(|buffer| Some(&buffers[buffer.index()].0[..]));
+                        let positions = reader
+                            .read_positions()
+                            .ok_or(anyhow!("{} has a primitive without positions", file))?
+                            .map(|position| transform.transform_point(position.into()).into())
+                            .collect_vec();
+                        let tex_coords = match reader.read_tex_coords(0) {
+                            Some(tex_coords) => tex_coords.into_f32().collect_vec(),
+                            None => vec![[0., 0.]; positions.len()],
+                        };
+                        let indices = match reader.read_indices() {
+                            Some(indices) => indices.into_u32().collect_vec(),
+                            None => (0..positions.len() as u32).collect_vec(),
+                        };
+                        mesh_data.append(positions, tex_coords, indices)
+                    })?;
+                }
+                nodes.extend(node.children().map(|child| (child, transform)));
+            }
+        } else {
+            return Err(anyhow!("{} isn't an obj or gltf file", file));
+        }
+        Ok(mesh_data)
+    }
+
+    fn append(
+        &mut self,
+        mut positions: Vec<[f32; 3]>,
+        mut tex_coords: Vec<[f32; 2]>,
+        indices: Vec<u32>,
+    ) -> anyhow::Result<()> {
+        let start = self.positions.len();
+        if start + positions.len() > u32::MAX as usize {
+            return Err(anyhow!("{} has more than {} vertices", self.file, u32::MAX));
+        }
+        tex_coords.resize(positions.len(), [0., 0.]);
+        self.indices.extend(
+            indices
+                .into_iter()
+                .map(|index| (index as usize + start) as u32),
+        );
+        self.positions.append(&mut positions);
+        self.tex_coords.append(&mut tex_coords);
+        Ok(())
+    }
+}
+
+/// Looks `file` up in the game folder's meshes and parses it. A file that can't be loaded
+/// is logged and gives back an empty mesh so it isn't parsed again every frame.
+pub fn find_static_mesh(meshes: &[(String, Arc<[u8]>)], file: &str) -> Arc<StaticMeshData> {
+    Arc::new(
+        meshes
+            .iter()
+            .find(|(name, _)| name == file)
+            .ok_or(anyhow!("{} isn't in the meshes folder", file))
+            .and_then(|(name, data)| StaticMeshData::from_bytes(name, data))
+            .unwrap_or_else(|err| {
+                log::warn!("couldn't load static mesh: {}", err);
+                StaticMeshData {
+                    file: file.to_string(),
+                    ..Default::default()
+                }
+            }),
+    )
+}
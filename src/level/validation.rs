@@ -0,0 +1,177 @@
+use std::fmt;
+
+use cgmath::{InnerSpace, MetricSpace, Vector2};
+
+/// Points closer than this are treated as the same point.
+const EPSILON: f32 = 1e-4;
+
+/// Why a polygon can't be triangulated or doesn't fit where it's used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolygonError {
+    TooFewPoints(usize),
+    DuplicatePoint(usize, usize),
+    /// Two edges cross or overlap, edge `i` goes from point `i` to the next one.
+    SelfIntersecting(usize, usize),
+    /// All the points are on one line.
+    NoArea,
+    /// The triangulation itself failed even though the outline looked fine.
+    Triangulation,
+    /// A hole or pit that isn't completely inside the room's walls.
+    OutsideRoom,
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolygonError::TooFewPoints(count) => {
+                write!(f, "needs at least 3 points but has {}", count)
+            }
+            PolygonError::DuplicatePoint(a, b) => write!(f, "points {} and {} are the same", a, b),
+            PolygonError::SelfIntersecting(a, b) => write!(f, "edges {} and {} cross", a, b),
+            PolygonError::NoArea => write!(f, "all points are in a line"),
+            PolygonError::Triangulation => write!(f, "couldn't be triangulated"),
+            PolygonError::OutsideRoom => write!(f, "goes outside the room's walls"),
+        }
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+/// Checks that `points` make a simple polygon that can be triangulated.
+pub fn validate_polygon(points: &[Vector2<f32>]) -> Result<(), PolygonError> {
+    let len = points.len();
+    if len < 3 {
+        return Err(PolygonError::TooFewPoints(len));
+    }
+    for a in 0..len {
+        for b in a + 1..len {
+            if points[a].distance(points[b]) < EPSILON {
+                return Err(PolygonError::DuplicatePoint(a, b));
+            }
+        }
+    }
+    let edge = |i: usize| (points[i], points[(i + 1) % len]);
+    for a in 0..len {
+        for b in a + 1..len {
+            let (a_start, a_end) = edge(a);
+            let (b_start, b_end) = edge(b);
+            let crosses = if b == a + 1 {
+                folds_back(a_start, a_end, b_end)
+            } else if a == 0 && b == len - 1 {
+                folds_back(b_start, a_start, a_end)
+            } else {
+                segments_touch(a_start, a_end, b_start, b_end)
+            };
+            if crosses {
+                return Err(PolygonError::SelfIntersecting(a, b));
+            }
+        }
+    }
+    let area = (0..len).fold(0., |acc, i| acc + points[i].perp_dot(points[(i + 1) % len]));
+    if area.abs() < EPSILON {
+        return Err(PolygonError::NoArea);
+    }
+    Ok(())
+}
+
+/// Whether the edges `a`-`b` and `b`-`c` double back over each other.
+fn folds_back(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>) -> bool {
+    let (first, second) = (b - a, c - b);
+    first.perp_dot(second).abs() < EPSILON * first.magnitude() * second.magnitude()
+        && first.dot(second) < 0.
+}
+
+fn segments_touch(
+    a_start: Vector2<f32>,
+    a_end: Vector2<f32>,
+    b_start: Vector2<f32>,
+    b_end: Vector2<f32>,
+) -> bool {
+    let side = |start: Vector2<f32>, end: Vector2<f32>, point: Vector2<f32>| {
+        let cross = (end - start).perp_dot(point - start);
+        if cross.abs() < EPSILON {
+            0.
+        } else {
+            cross.signum()
+        }
+    };
+    let on_segment = |start: Vector2<f32>, end: Vector2<f32>, point: Vector2<f32>| {
+        point.x >= start.x.min(end.x) - EPSILON
+            && point.x <= start.x.max(end.x) + EPSILON
+            && point.y >= start.y.min(end.y) - EPSILON
+            && point.y <= start.y.max(end.y) + EPSILON
+    };
+    let sides = [
+        side(a_start, a_end, b_start),
+        side(a_start, a_end, b_end),
+        side(b_start, b_end, a_start),
+        side(b_start, b_end, a_end),
+    ];
+    if sides[0] * sides[1] < 0. && sides[2] * sides[3] < 0. {
+        return true;
+    }
+    (sides[0] == 0. && on_segment(a_start, a_end, b_start))
+        || (sides[1] == 0. && on_segment(a_start, a_end, b_end))
+        || (sides[2] == 0. && on_segment(b_start, b_end, a_start))
+        || (sides[3] == 0. && on_segment(b_start, b_end, a_end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(points: &[(f32, f32)]) -> Vec<Vector2<f32>> {
+        points.iter().map(|&(x, y)| Vector2::new(x, y)).collect()
+    }
+
+    #[test]
+    fn accepts_simple_polygons_either_way_round() {
+        let square = points(&[(0., 0.), (1., 0.), (1., 1.), (0., 1.)]);
+        assert_eq!(validate_polygon(&square), Ok(()));
+        let reversed = square.into_iter().rev().collect::<Vec<_>>();
+        assert_eq!(validate_polygon(&reversed), Ok(()));
+        let l_shape = points(&[(0., 0.), (2., 0.), (2., 1.), (1., 1.), (1., 2.), (0., 2.)]);
+        assert_eq!(validate_polygon(&l_shape), Ok(()));
+    }
+
+    #[test]
+    fn rejects_too_few_points() {
+        assert_eq!(
+            validate_polygon(&points(&[(0., 0.), (1., 0.)])),
+            Err(PolygonError::TooFewPoints(2))
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_points() {
+        let points = points(&[(0., 0.), (1., 0.), (1., 1.), (1., 0.)]);
+        assert_eq!(
+            validate_polygon(&points),
+            Err(PolygonError::DuplicatePoint(1, 3))
+        );
+    }
+
+    #[test]
+    fn rejects_crossing_edges() {
+        let bow_tie = points(&[(0., 0.), (1., 1.), (1., 0.), (0., 1.)]);
+        assert_eq!(
+            validate_polygon(&bow_tie),
+            Err(PolygonError::SelfIntersecting(0, 2))
+        );
+    }
+
+    #[test]
+    fn rejects_edges_that_fold_back() {
+        let spike = points(&[(0., 0.), (2., 0.), (1., 0.), (1., 1.)]);
+        assert!(matches!(
+            validate_polygon(&spike),
+            Err(PolygonError::SelfIntersecting(..))
+        ));
+    }
+
+    #[test]
+    fn rejects_points_in_a_line() {
+        let line = points(&[(0., 0.), (1., 1.), (2., 2.)]);
+        assert!(validate_polygon(&line).is_err());
+    }
+}
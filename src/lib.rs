@@ -1,9 +1,12 @@
 pub mod application_state;
 pub mod camer_control;
+pub mod export;
 pub mod level;
 pub mod more_stolen_code;
 pub mod stolen_code_to_update_dependencies;
 pub mod renderer;
+#[cfg(test)]
+mod test_utils;
 
 pub trait ModuloSignedExt {
     fn modulo(&self, n: Self) -> Self;
@@ -66,7 +66,7 @@ pub struct State {
 }
 
 impl State {
-    pub async fn new(window: Window, vertices: Vec<Vertex>, indices: Vec<u16>) -> Self {
+    pub async fn new(window: Window, vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
         let size = window.inner_size();
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -361,17 +361,7 @@ impl State {
             let mut mesh_map: HashMap<TextureId, Mesh> = HashMap::new();
             meshs.into_iter().for_each(|mesh| {
                 if let Some(mesh_to_add_to) = mesh_map.get_mut(&mesh.textrure) {
-                    let vertices_already_in = mesh_to_add_to.vertices.len() as u16;
-                    mesh_to_add_to.indices.append(
-                        &mut mesh
-                            .indices
-                            .into_iter()
-                            .map(|index| index + vertices_already_in)
-                            .collect(),
-                    );
-                    mesh_to_add_to
-                        .vertices
-                        .append(&mut mesh.vertices.into_iter().collect());
+                    mesh_to_add_to.combine(mesh);
                 } else {
                     mesh_map.insert(mesh.textrure.clone(), mesh);
                 }
@@ -462,7 +452,7 @@ impl State {
                 render_pass.set_vertex_buffer(0, mesh_pass.vertex_buffer.slice(..));
                 render_pass.set_bind_group(0, &mesh_pass.texture_bindgroup, &[]);
                 render_pass
-                    .set_index_buffer(mesh_pass.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                    .set_index_buffer(mesh_pass.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
                 render_pass.draw_indexed(0..mesh_pass.num_indecies, 0, 0..1);
             }
         }
@@ -8,11 +8,15 @@ var<uniform> camera: Camera;
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tangent: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tangent: vec4<f32>,
 }
 
 @vertex
@@ -21,6 +25,8 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
     out.tex_coords = model.tex_coords;
+    out.normal = model.normal;
+    out.tangent = model.tangent;
     out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
     return out;
 }
@@ -34,5 +40,8 @@ var s_diffuse: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, fract(in.tex_coords));
+    // a soft light from above so faces can be told apart
+    let light = 0.75 + 0.25 * dot(normalize(in.normal), normalize(vec3<f32>(0.3, 1.0, 0.5)));
+    let color = textureSample(t_diffuse, s_diffuse, fract(in.tex_coords));
+    return vec4<f32>(color.rgb * light, color.a);
 }
\ No newline at end of file
@@ -0,0 +1,9 @@
+use std::{fs, path::PathBuf};
+
+/// An empty folder under the system temp dir, named after the test using it.
+pub(crate) fn temp_folder(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("r3dget_test_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+    path
+}